@@ -32,9 +32,32 @@ where
     fn run(window_size: (u32, u32), game: &mut Game<R>) -> Result<(), Self::ErrorType> {
         let size = (
             window_size.0.try_into().unwrap(),
+            window_size.1.try_into().unwrap(),
+        );
+        crate::terminal_graphics::run(size, game, None)
+    }
+}
+
+/// The terminal backend printing cells as their `PrintableDataType`
+/// characters, in colour where the terminal has it.
+#[cfg(feature = "graphics-terminal")]
+pub struct TerminalTextBackend {}
+
+#[cfg(feature = "graphics-terminal")]
+impl<R> Backend<R> for TerminalTextBackend
+where
+    R: RuleSet,
+    R::Data: ColoredDataType + crate::PrintableDataType,
+{
+    type ErrorType = std::io::Error;
+
+    fn run(window_size: (u32, u32), game: &mut Game<R>) -> Result<(), Self::ErrorType> {
+        let size = (
             window_size.0.try_into().unwrap(),
+            window_size.1.try_into().unwrap(),
         );
-        crate::terminal_graphics::run(size, game)
+        let chars = <R::Data as crate::PrintableDataType>::get_char;
+        crate::terminal_graphics::run(size, game, Some(chars))
     }
 }
 
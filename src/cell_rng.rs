@@ -0,0 +1,93 @@
+use rand::RngCore;
+
+use crate::IndexType;
+
+/// SplitMix64 finaliser, used both to derive the starting state and as the
+/// output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Random stream for one cell in one generation. The stream only depends on
+/// the game seed, the generation and the cell coordinates, so results are
+/// the same however the grid is split between threads.
+#[derive(Clone)]
+pub struct CellRng {
+    state: u64,
+}
+
+impl CellRng {
+    pub fn new(seed: u64, generation: u64, position: IndexType) -> CellRng {
+        let coordinates = (position.0 as u32 as u64) << 32 | position.1 as u32 as u64;
+        CellRng {
+            state: mix(mix(mix(seed) ^ generation) ^ coordinates),
+        }
+    }
+
+    /// Stream for a whole generation, for choices that are not tied to one
+    /// cell such as the order of updates.
+    pub fn for_generation(seed: u64, generation: u64) -> CellRng {
+        CellRng {
+            state: mix(mix(seed) ^ generation),
+        }
+    }
+}
+
+impl RngCore for CellRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.state)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_follow_splitmix64() {
+        let mut rng = CellRng { state: 0 };
+        let outputs = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+        assert_eq!(
+            outputs,
+            [0xe220a8397b1dcdaf, 0x6e789e6aa1b965f4, 0x06c45d188009454f]
+        );
+    }
+
+    /// Saved runs replay only while these stay the same.
+    #[test]
+    fn streams_are_stable() {
+        let expected = [
+            (42, 0, 0x9d591bb7266b13f3, 0x733a550e28bd9590),
+            (42, 1, 0x196e01eba8e0a0d4, 0xf9c2c0931cd997a5),
+            (42, 1000, 0x6cdcbad1836b8be5, 0x53856717b472abd3),
+        ];
+        for (seed, generation, first, second) in expected {
+            let mut rng = CellRng::for_generation(seed, generation);
+            assert_eq!((rng.next_u64(), rng.next_u64()), (first, second));
+        }
+        let mut rng = CellRng::new(42, 7, (-3, 5));
+        assert_eq!(
+            (rng.next_u64(), rng.next_u64()),
+            (0xec8902ef9201358e, 0xa4b9676afb30e045)
+        );
+    }
+}
@@ -0,0 +1,686 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{ColoredDataType, FrameLimiter, GError, GResult, Game, RuleSet, Viewport, DEFAULT_FPS};
+
+/// The bindings every backend starts with, in the format read by
+/// `Bindings::parse`.
+pub const DEFAULT_BINDINGS: &str = "\
+play_pause = Space
+step = Right
+step_back = Left
+faster = +, =, ]
+slower = -, [
+max_speed = m
+reset = Backspace
+randomize = r
+screenshot = i
+record = v
+quit = Escape, q
+fps = f
+layer = l
+slice_up = PageUp
+slice_down = PageDown
+clear = Delete
+brush_0 = 0
+brush_1 = 1
+brush_2 = 2
+brush_3 = 3
+brush_4 = 4
+brush_5 = 5
+brush_6 = 6
+brush_7 = 7
+brush_8 = 8
+brush_9 = 9
+pan_left = Shift+Left
+pan_right = Shift+Right
+pan_up = Shift+Up
+pan_down = Shift+Down
+zoom_in = .
+zoom_out = Comma
+fit_pattern = z
+fit_all = Home
+";
+
+/// Generations per second start at the first and are doubled and halved
+/// between the others.
+const DEFAULT_SPEED: f64 = 8.0;
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 4096.0;
+
+/// Share of a frame that generations may take, leaving the rest for input
+/// and drawing.
+const STEP_BUDGET: f64 = 0.75;
+
+/// Something a key can ask of any backend.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    PlayPause,
+    /// Takes one generation while paused.
+    Step,
+    StepBack,
+    SpeedUp,
+    SpeedDown,
+    /// Runs as many generations as fit in each frame, or goes back to the
+    /// speed set.
+    MaxSpeed,
+    Reset,
+    Randomize,
+    Screenshot,
+    /// Starts or stops saving every generation as a picture.
+    Record,
+    Quit,
+    ToggleFps,
+    NextLayer,
+    SliceUp,
+    SliceDown,
+    Clear,
+    Brush(u8),
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    FitPattern,
+    FitAll,
+}
+
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("play_pause", Action::PlayPause),
+    ("step", Action::Step),
+    ("step_back", Action::StepBack),
+    ("faster", Action::SpeedUp),
+    ("slower", Action::SpeedDown),
+    ("max_speed", Action::MaxSpeed),
+    ("reset", Action::Reset),
+    ("randomize", Action::Randomize),
+    ("screenshot", Action::Screenshot),
+    ("record", Action::Record),
+    ("quit", Action::Quit),
+    ("fps", Action::ToggleFps),
+    ("layer", Action::NextLayer),
+    ("slice_up", Action::SliceUp),
+    ("slice_down", Action::SliceDown),
+    ("clear", Action::Clear),
+    ("pan_left", Action::PanLeft),
+    ("pan_right", Action::PanRight),
+    ("pan_up", Action::PanUp),
+    ("pan_down", Action::PanDown),
+    ("zoom_in", Action::ZoomIn),
+    ("zoom_out", Action::ZoomOut),
+    ("fit_pattern", Action::FitPattern),
+    ("fit_all", Action::FitAll),
+];
+
+impl Action {
+    /// Reads the action names of binding files, such as `play_pause` or
+    /// `brush_3`.
+    pub fn parse(name: &str) -> Option<Action> {
+        if let Some(state) = name.strip_prefix("brush_") {
+            return state.parse().ok().map(Action::Brush);
+        }
+        ACTION_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, action)| action)
+    }
+}
+
+/// A key as backends and binding files name it, whatever the windowing
+/// library.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    /// Letters, lower case, digits and symbols.
+    Char(char),
+    Space,
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    F(u8),
+}
+
+impl Key {
+    /// Reads a single character, such as `q` or `]`, or a key name such as
+    /// `Space`, `PageUp` or `F5`. Names ignore case and follow the key codes
+    /// of winit, piston and SFML as printed with `{:?}`, so `Return`,
+    /// `ArrowLeft`, `KeyA`, `Key5`, `Digit5`, `Num5`, `D5`, `Equals` or
+    /// `Hyphen` are all understood.
+    pub fn parse(name: &str) -> Option<Key> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(Key::Char(c.to_ascii_lowercase()));
+        }
+        let lower = name.to_ascii_lowercase();
+        // Letters and digits behind a prefix, as in `KeyA` or `Digit5`.
+        for prefix in ["key", "digit", "num", "d"] {
+            let mut rest = lower.strip_prefix(prefix).unwrap_or_default().chars();
+            if let (Some(c), None) = (rest.next(), rest.next()) {
+                if c.is_ascii_alphanumeric() {
+                    return Some(Key::Char(c));
+                }
+            }
+        }
+        if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            return Some(Key::F(n));
+        }
+        let key = match lower.as_str() {
+            "space" => Key::Space,
+            "enter" | "return" => Key::Enter,
+            "escape" | "esc" => Key::Escape,
+            "backspace" | "back" => Key::Backspace,
+            "delete" | "del" => Key::Delete,
+            "tab" => Key::Tab,
+            "left" | "arrowleft" => Key::Left,
+            "right" | "arrowright" => Key::Right,
+            "up" | "arrowup" => Key::Up,
+            "down" | "arrowdown" => Key::Down,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "equal" | "equals" => Key::Char('='),
+            "minus" | "hyphen" => Key::Char('-'),
+            "plus" => Key::Char('+'),
+            "comma" => Key::Char(','),
+            "period" => Key::Char('.'),
+            "slash" => Key::Char('/'),
+            "lbracket" | "leftbracket" | "bracketleft" => Key::Char('['),
+            "rbracket" | "rightbracket" | "bracketright" => Key::Char(']'),
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyPress {
+    pub key: Key,
+    pub shift: bool,
+}
+
+impl KeyPress {
+    pub fn new(key: Key, shift: bool) -> KeyPress {
+        KeyPress { key, shift }
+    }
+
+    /// Reads a key name, optionally behind `Shift+`.
+    pub fn parse(name: &str) -> Option<KeyPress> {
+        let name = name.trim();
+        match name.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("shift+") => {
+                Key::parse(&name[6..]).map(|key| KeyPress::new(key, true))
+            }
+            _ => Key::parse(name).map(|key| KeyPress::new(key, false)),
+        }
+    }
+
+    /// A key code of a windowing library, read from its `{:?}` name.
+    pub fn from_code<K: Debug>(code: &K, shift: bool) -> Option<KeyPress> {
+        Key::parse(&format!("{:?}", code)).map(|key| KeyPress::new(key, shift))
+    }
+}
+
+/// Which action each key press asks for.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    actions: HashMap<KeyPress, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Bindings {
+            actions: HashMap::new(),
+        };
+        bindings
+            .read(DEFAULT_BINDINGS)
+            .expect("default bindings are valid");
+        bindings
+    }
+}
+
+impl Bindings {
+    /// Reads lines such as `quit = Escape, q` that bind an action to keys,
+    /// as in `DEFAULT_BINDINGS`. Actions named in `text` lose their default
+    /// keys and the others keep them. Lines starting with `#` are comments.
+    pub fn parse(text: &str) -> GResult<Bindings> {
+        let mut bindings = Bindings::default();
+        bindings.read(text)?;
+        Ok(bindings)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> GResult<Bindings> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| GError::FileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Bindings::parse(&text)
+    }
+
+    fn read(&mut self, text: &str) -> GResult<()> {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'));
+        for (line, l) in lines {
+            let err = |message: String| GError::BindingError { line, message };
+            let (name, keys) = l
+                .split_once('=')
+                .ok_or_else(|| err("expected 'action = keys'".to_string()))?;
+            let name = name.trim();
+            let action =
+                Action::parse(name).ok_or_else(|| err(format!("unknown action '{}'", name)))?;
+            self.actions.retain(|_, a| *a != action);
+            for key in keys.split(',') {
+                let press = KeyPress::parse(key)
+                    .ok_or_else(|| err(format!("unknown key '{}'", key.trim())))?;
+                self.actions.insert(press, action);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn bind(&mut self, press: KeyPress, action: Action) {
+        self.actions.insert(press, action);
+    }
+
+    /// The action of `press`. Keys bound without Shift also work with it,
+    /// unless Shift and the key are bound to something else.
+    pub fn action(&self, press: KeyPress) -> Option<Action> {
+        self.actions.get(&press).copied().or_else(|| {
+            let unshifted = KeyPress::new(press.key, false);
+            press.shift.then(|| self.actions.get(&unshifted).copied())?
+        })
+    }
+}
+
+/// What every backend does with key presses: playing and pausing, stepping,
+/// speed, editing the grid, saving pictures and moving the viewport.
+/// Backends translate their key events into `KeyPress`es, call `press` and
+/// `update`, and handle the actions that need them, such as `Quit`.
+pub struct Controller {
+    bindings: Bindings,
+    playing: bool,
+    show_fps: bool,
+    /// Generations per second while playing.
+    speed: f64,
+    max_speed: bool,
+    /// Generations due but not taken yet, and when they were counted.
+    due: f64,
+    counted: Option<Instant>,
+    limiter: FrameLimiter,
+    recording: Option<Recording>,
+    message: String,
+}
+
+struct Recording {
+    dir: PathBuf,
+    frame: u32,
+}
+
+impl Controller {
+    pub fn new(bindings: Bindings) -> Controller {
+        Controller {
+            bindings,
+            playing: false,
+            show_fps: false,
+            speed: DEFAULT_SPEED,
+            max_speed: false,
+            due: 0.0,
+            counted: None,
+            limiter: FrameLimiter::new(DEFAULT_FPS),
+            recording: None,
+            message: String::new(),
+        }
+    }
+
+    /// Frames per second to draw at, which does not change how many
+    /// generations a second take.
+    pub fn with_fps(mut self, fps: u32) -> Controller {
+        self.limiter = FrameLimiter::new(fps);
+        self
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn show_fps(&self) -> bool {
+        self.show_fps
+    }
+
+    /// Generations per second while playing.
+    pub fn get_speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn is_max_speed(&self) -> bool {
+        self.max_speed
+    }
+
+    /// The speed as the status line and window titles show it.
+    pub fn speed_text(&self) -> String {
+        if self.max_speed {
+            "max speed".to_string()
+        } else {
+            format!("{} gen/s", self.speed)
+        }
+    }
+
+    /// Time left until the next frame is due, for backends that wait for
+    /// events with a timeout.
+    pub fn time_to_frame(&self) -> Duration {
+        self.limiter.remaining()
+    }
+
+    pub fn next_frame(&self) -> Instant {
+        self.limiter.next_frame()
+    }
+
+    /// Whether the next frame is due, in which case it is started and the
+    /// backend should `update` and draw.
+    pub fn frame_due(&mut self) -> bool {
+        self.limiter.frame_due()
+    }
+
+    /// Sleeps until the next frame is due, for backends that draw as fast as
+    /// they can.
+    pub fn wait_for_frame(&mut self) {
+        self.limiter.wait();
+    }
+
+    /// What the last screenshot or recording did, for the status line or
+    /// the window title.
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// `name - seed N - speed`, followed by the message if there is one.
+    pub fn title<R: RuleSet>(&self, name: &str, game: &Game<R>) -> String {
+        let title = format!(
+            "{} - seed {} - {}",
+            name,
+            game.get_seed(),
+            self.speed_text()
+        );
+        if self.message.is_empty() {
+            title
+        } else {
+            format!("{} - {}", title, self.message)
+        }
+    }
+
+    /// Carries out the action bound to `press` and returns it, so the
+    /// backend can do its part.
+    pub fn press<R>(
+        &mut self,
+        press: KeyPress,
+        game: &mut Game<R>,
+        viewport: &mut Viewport,
+    ) -> Option<Action>
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let action = self.bindings.action(press)?;
+        self.apply(action, game, viewport);
+        Some(action)
+    }
+
+    pub fn apply<R>(&mut self, action: Action, game: &mut Game<R>, viewport: &mut Viewport)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let center = viewport.center();
+        match action {
+            Action::PlayPause => self.playing ^= true,
+            Action::Step if !self.playing => self.step(game),
+            Action::Step => {}
+            Action::StepBack => {
+                self.playing = false;
+                game.step_back();
+            }
+            Action::SpeedUp => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            Action::SpeedDown => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            Action::MaxSpeed => self.max_speed ^= true,
+            Action::Reset => game.reset(),
+            Action::Randomize => game.randomize(rand::random()),
+            Action::Screenshot => {
+                let path = format!("gol-{}-{}.png", game.get_seed(), game.get_generation());
+                self.message = match save_image(game, Path::new(&path)) {
+                    Ok(()) => format!("saved {}", path),
+                    Err(e) => e,
+                };
+            }
+            Action::Record => self.toggle_recording(game),
+            Action::Quit => {}
+            Action::ToggleFps => self.show_fps ^= true,
+            Action::NextLayer => game.next_layer(),
+            Action::SliceUp => game.set_slice(game.get_slice() as i32 + 1),
+            Action::SliceDown => game.set_slice(game.get_slice() as i32 - 1),
+            Action::Clear => game.clear(),
+            Action::Brush(state) => game.set_brush(state as usize),
+            Action::PanLeft => viewport.pan_steps(-1, 0),
+            Action::PanRight => viewport.pan_steps(1, 0),
+            Action::PanUp => viewport.pan_steps(0, -1),
+            Action::PanDown => viewport.pan_steps(0, 1),
+            Action::ZoomIn => viewport.zoom_in(center),
+            Action::ZoomOut => viewport.zoom_out(center),
+            Action::FitPattern => viewport.fit_pattern(game),
+            Action::FitAll => viewport.fit_all(),
+        }
+    }
+
+    /// Runs the generations due since the last call while playing, however
+    /// often backends call it. Generations stop once they have taken most
+    /// of a frame, so a speed the rules cannot keep up with slows down
+    /// instead of freezing the window; at max speed they always run that
+    /// long.
+    pub fn update<R>(&mut self, game: &mut Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let now = Instant::now();
+        let last = self.counted.replace(now);
+        if !self.playing {
+            self.due = 0.0;
+            return;
+        }
+        let elapsed = last.map_or(Duration::ZERO, |last| now - last);
+        self.due += elapsed.as_secs_f64() * self.speed;
+        let steps = if self.max_speed {
+            u64::MAX
+        } else {
+            self.due as u64
+        };
+        let until = now + self.limiter.get_interval().mul_f64(STEP_BUDGET);
+        for _ in 0..steps {
+            self.step(game);
+            if Instant::now() >= until {
+                break;
+            }
+        }
+        // Running behind is not caught up on later.
+        self.due = self.due.fract();
+    }
+
+    fn step<R>(&mut self, game: &mut Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        game.next_step();
+        if self.recording.is_some() {
+            self.record_frame(game);
+        }
+    }
+
+    /// Recordings go to a new directory named after the seed and the
+    /// generation they start at, one numbered picture per generation.
+    fn toggle_recording<R>(&mut self, game: &Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        if let Some(recording) = self.recording.take() {
+            self.message = format!(
+                "recorded {} frames to {}",
+                recording.frame,
+                recording.dir.display()
+            );
+            return;
+        }
+        let dir = PathBuf::from(format!("gol-{}-{}", game.get_seed(), game.get_generation()));
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.message = format!("cannot record to {}: {}", dir.display(), e);
+            return;
+        }
+        self.message = format!("recording to {}", dir.display());
+        self.recording = Some(Recording { dir, frame: 0 });
+        self.record_frame(game);
+    }
+
+    fn record_frame<R>(&mut self, game: &Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        let path = recording
+            .dir
+            .join(format!("frame-{:05}.png", recording.frame));
+        match save_image(game, &path) {
+            Ok(()) => recording.frame += 1,
+            Err(e) => {
+                self.message = e;
+                self.recording = None;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "export-png")]
+fn save_image<R>(game: &Game<R>, path: &Path) -> Result<(), String>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    game.save_png(path).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "export-png"))]
+fn save_image<R>(_game: &Game<R>, _path: &Path) -> Result<(), String>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    Err("saving pictures needs the export-png feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_of_every_library() {
+        let names = [
+            // winit
+            ("KeyA", Key::Char('a')),
+            ("Digit5", Key::Char('5')),
+            ("ArrowLeft", Key::Left),
+            ("Equal", Key::Char('=')),
+            ("Minus", Key::Char('-')),
+            ("BracketLeft", Key::Char('[')),
+            ("Enter", Key::Enter),
+            // piston
+            ("A", Key::Char('a')),
+            ("D5", Key::Char('5')),
+            ("Return", Key::Enter),
+            ("Equals", Key::Char('=')),
+            ("LeftBracket", Key::Char('[')),
+            // SFML
+            ("Num5", Key::Char('5')),
+            ("Hyphen", Key::Char('-')),
+            ("LBracket", Key::Char('[')),
+            ("Escape", Key::Escape),
+            ("PageDown", Key::PageDown),
+            ("Delete", Key::Delete),
+            ("Down", Key::Down),
+            ("F12", Key::F(12)),
+        ];
+        for (name, key) in names {
+            assert_eq!(Key::parse(name), Some(key), "{}", name);
+        }
+        assert_eq!(Key::parse("Unknown"), None);
+    }
+
+    #[test]
+    fn shifted_presses() {
+        assert_eq!(
+            KeyPress::parse(" shift+Left "),
+            Some(KeyPress::new(Key::Left, true))
+        );
+        assert_eq!(
+            KeyPress::parse("q"),
+            Some(KeyPress::new(Key::Char('q'), false))
+        );
+        #[derive(Debug)]
+        enum Code {
+            ArrowUp,
+        }
+        assert_eq!(
+            KeyPress::from_code(&Code::ArrowUp, true),
+            KeyPress::parse("Shift+Up")
+        );
+    }
+
+    #[test]
+    fn defaults_and_overrides() {
+        let press = |name| KeyPress::parse(name).unwrap();
+        let defaults = Bindings::default();
+        assert_eq!(defaults.action(press("Space")), Some(Action::PlayPause));
+        assert_eq!(defaults.action(press("Shift+Left")), Some(Action::PanLeft));
+        assert_eq!(defaults.action(press("Shift+r")), Some(Action::Randomize));
+        assert_eq!(defaults.action(press("3")), Some(Action::Brush(3)));
+
+        let bindings = Bindings::parse("# mine\nquit = x\n\nbrush_2 = F2\n").unwrap();
+        assert_eq!(bindings.action(press("x")), Some(Action::Quit));
+        assert_eq!(bindings.action(press("q")), None);
+        assert_eq!(bindings.action(press("Escape")), None);
+        assert_eq!(bindings.action(press("F2")), Some(Action::Brush(2)));
+        assert_eq!(bindings.action(press("2")), None);
+        assert_eq!(bindings.action(press("Space")), Some(Action::PlayPause));
+    }
+
+    #[test]
+    fn binding_errors_name_the_line() {
+        for text in [
+            "quit = x\nfly = y",
+            "quit = x\nquit = Nowhere",
+            "quit = x\nquit",
+        ] {
+            match Bindings::parse(text) {
+                Err(GError::BindingError { line, .. }) => assert_eq!(line, 2, "{}", text),
+                _ => panic!("expected an error for {:?}", text),
+            }
+        }
+    }
+}
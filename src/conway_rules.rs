@@ -1,14 +1,15 @@
 use game_of_life::{DataType, ColoredDataType, RandomInit, RuleSet, PrintableDataType, Color};
+use rand::Rng;
 use std::collections::VecDeque;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ClassicConway {}
 
 impl RuleSet for ClassicConway {
     type Data = BoolData;
     const SOURCE_SIZE: u8 = 3;
 
-    fn next(source: &[&BoolData]) -> BoolData {
+    fn next(&self, source: &[&BoolData]) -> BoolData {
         let me = source[4].value;
         let all: i32 = source.iter().map(|x| x.value as i32).sum();
         let neighbours = all - me as i32;
@@ -18,6 +19,10 @@ impl RuleSet for ClassicConway {
             _ => BoolData { value: false }
         }
     }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![BoolData { value: false }, BoolData { value: true }]
+    }
 }
 
 /*impl InitRuleSet for ClassicConway{
@@ -35,8 +40,19 @@ pub struct BoolData {
 impl DataType for BoolData {}
 
 impl RandomInit for BoolData {
-    fn rnd() -> BoolData {
-        BoolData { value: rand::random::<bool>() }
+    fn rnd(rng: &mut impl Rng) -> BoolData {
+        BoolData { value: rng.gen::<bool>() }
+    }
+}
+
+impl BoolData {
+    pub fn is_alive(&self) -> bool {
+        self.value
+    }
+
+    /// A random cell that is alive with probability `density`.
+    pub fn rnd_with_density(rng: &mut impl Rng, density: f64) -> BoolData {
+        BoolData { value: rng.gen_bool(density.clamp(0.0, 1.0)) }
     }
 }
 
@@ -78,10 +94,10 @@ pub struct BoolHist {
 impl DataType for BoolHist {}
 
 impl RandomInit for BoolHist {
-    fn rnd() -> BoolHist {
+    fn rnd(rng: &mut impl Rng) -> BoolHist {
         BoolHist {
             history: VecDeque::from(vec![false; 5]),
-            current: rand::random::<bool>(),
+            current: rng.gen::<bool>(),
         }
     }
 }
@@ -98,14 +114,14 @@ impl ColoredDataType for BoolHist {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ConwayWithHistory {}
 
 impl RuleSet for ConwayWithHistory {
     type Data = BoolHist;
     const SOURCE_SIZE: u8 = 3;
 
-    fn next(source: &[&BoolHist]) -> BoolHist {
+    fn next(&self, source: &[&BoolHist]) -> BoolHist {
         let me = source[4];
         let all: i32 = source.iter().map(|x| x.current as i32).sum();
         let neighbours = all - me.current as i32;
@@ -119,6 +135,15 @@ impl RuleSet for ConwayWithHistory {
         history.pop_front();
         BoolHist { current, history }
     }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        [false, true]
+            .map(|current| BoolHist {
+                current,
+                history: VecDeque::from(vec![false; 5]),
+            })
+            .to_vec()
+    }
 }
 
 //**************************************************************
@@ -132,8 +157,8 @@ pub struct ColorData {
 impl DataType for ColorData {}
 
 impl RandomInit for ColorData {
-    fn rnd() -> Self {
-        ColorData { r: rand::random::<bool>(), g: rand::random::<bool>(), b: rand::random::<bool>() }
+    fn rnd(rng: &mut impl Rng) -> Self {
+        ColorData { r: rng.gen::<bool>(), g: rng.gen::<bool>(), b: rng.gen::<bool>() }
     }
 }
 
@@ -143,14 +168,14 @@ impl ColoredDataType for ColorData {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ConwayColors {}
 
 impl RuleSet for ConwayColors {
     type Data = ColorData;
     const SOURCE_SIZE: u8 = 3;
 
-    fn next(source: &[&ColorData]) -> ColorData {
+    fn next(&self, source: &[&ColorData]) -> ColorData {
         let me = source[4];
         let all = source.iter().fold((0, 0, 0), |acc, d| (acc.0 + d.r as i8, acc.1 + d.g as i8, acc.2 + d.b as i8));
         let neighbours = (all.0 - me.r as i8, all.1 - me.g as i8, all.2 - me.b as i8);
@@ -170,4 +195,10 @@ impl RuleSet for ConwayColors {
 
         ColorData { r, g, b }
     }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        (0..8)
+            .map(|c| ColorData { r: c & 1 != 0, g: c & 2 != 0, b: c & 4 != 0 })
+            .collect()
+    }
 }
\ No newline at end of file
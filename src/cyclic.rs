@@ -0,0 +1,287 @@
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, GError, GResult, Neighborhood, RuleSet};
+
+const MAX_RANGE: u8 = 10;
+
+/// Named rules from Fisch, Gravner and Griffeath's survey of cyclic
+/// automata, in the `R/T/C/N` notation read by `Cyclic::parse`.
+pub const CYCLIC_PRESETS: &[(&str, &str)] = &[
+    ("313", "R1/T3/C3/NM"),
+    ("amoeba", "R3/T10/C2/NN"),
+    ("cca", "R1/T1/C14/NN"),
+    ("cubism", "R2/T5/C3/NN"),
+    ("cyclic-spirals", "R3/T5/C8/NM"),
+    ("imperfect", "R1/T2/C4/NM"),
+    ("lava-lamp", "R2/T10/C3/NM"),
+    ("perfect-spirals", "R1/T3/C4/NM"),
+    ("rgb", "R1/T3/C3/NM"),
+    ("stripes", "R3/T4/C5/NN"),
+    ("turbulent-phase", "R2/T5/C8/NM"),
+];
+
+#[derive(Clone)]
+pub struct CyclicCell {
+    state: u8,
+    color: Color,
+}
+
+impl CyclicCell {
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+}
+
+impl DataType for CyclicCell {}
+
+impl ColoredDataType for CyclicCell {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+/// Cyclic cellular automaton, or rock-paper-scissors with `states`
+/// species: a cell in state `k` is eaten by state `k + 1` (wrapping around)
+/// once at least `threshold` of its neighbours within `range` are in that
+/// state.
+#[derive(Clone)]
+pub struct Cyclic {
+    states: u8,
+    threshold: u16,
+    range: u8,
+    neighborhood: Neighborhood,
+    // Positions of the neighbours in the source window.
+    neighbours: Vec<usize>,
+    palette: Vec<Color>,
+}
+
+impl Default for Cyclic {
+    fn default() -> Self {
+        Cyclic::rgb()
+    }
+}
+
+impl Cyclic {
+    /// `neighborhood` is Moore, von Neumann (a diamond for ranges above
+    /// one) or hexagonal, using the same skewed layout as rule tables.
+    pub fn new(
+        states: u8,
+        threshold: u16,
+        range: u8,
+        neighborhood: Neighborhood,
+    ) -> GResult<Cyclic> {
+        if states < 2 {
+            return Err(GError::RuleParameterError(
+                "a cyclic rule needs at least two states".to_string(),
+            ));
+        }
+        if !(1..=MAX_RANGE).contains(&range) {
+            return Err(GError::RuleParameterError(format!(
+                "range must be between 1 and {}",
+                MAX_RANGE
+            )));
+        }
+        let r = range as i32;
+        let side = 2 * r + 1;
+        let mut neighbours = Vec::new();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let inside = match neighborhood {
+                    Neighborhood::Moore => true,
+                    Neighborhood::VonNeumann => dx.abs() + dy.abs() <= r,
+                    Neighborhood::Hexagonal => (dx - dy).abs() <= r,
+                    Neighborhood::OneDimensional => {
+                        return Err(GError::RuleParameterError(
+                            "cyclic rules need a two-dimensional neighbourhood".to_string(),
+                        ))
+                    }
+                };
+                if inside && (dx, dy) != (0, 0) {
+                    neighbours.push(((dy + r) * side + dx + r) as usize);
+                }
+            }
+        }
+        if threshold == 0 || threshold as usize > neighbours.len() {
+            return Err(GError::RuleParameterError(format!(
+                "threshold must be between 1 and {}",
+                neighbours.len()
+            )));
+        }
+        Ok(Cyclic {
+            states,
+            threshold,
+            range,
+            neighborhood,
+            neighbours,
+            palette: (0..states).map(|s| rainbow(s, states)).collect(),
+        })
+    }
+
+    /// Reads rules like `R1/T3/C3/NM`: range, threshold, number of states
+    /// and `NM` Moore, `NN` von Neumann or `NH` hexagonal neighbourhood.
+    /// Missing parts default to `R1/T1/C3/NM`.
+    pub fn parse(rule: &str) -> GResult<Cyclic> {
+        let err = |message: &str| GError::RuleStringError {
+            rule: rule.to_string(),
+            message: message.to_string(),
+        };
+        let (mut range, mut threshold, mut states) = (1, 1, 3);
+        let mut neighborhood = Neighborhood::Moore;
+        for part in rule.trim().split(['/', ',']) {
+            let part = part.trim().to_ascii_uppercase();
+            let number = || part[1..].parse().map_err(|_| err("expected a number"));
+            match part.chars().next() {
+                Some('R') => range = number()?,
+                Some('T') => threshold = number()?,
+                Some('C') => states = number()?,
+                Some('N') => {
+                    neighborhood = match &part[1..] {
+                        "M" => Neighborhood::Moore,
+                        "N" => Neighborhood::VonNeumann,
+                        "H" => Neighborhood::Hexagonal,
+                        _ => return Err(err("unknown neighbourhood")),
+                    }
+                }
+                _ => return Err(err("expected R, T, C or N")),
+            }
+        }
+        if range > MAX_RANGE as u32 || threshold > u16::MAX as u32 || states > u8::MAX as u32 {
+            return Err(err("value out of range"));
+        }
+        Cyclic::new(states as u8, threshold as u16, range as u8, neighborhood)
+    }
+
+    pub fn preset(name: &str) -> Option<Cyclic> {
+        CYCLIC_PRESETS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, rule)| Cyclic::parse(rule).unwrap())
+    }
+
+    /// Three species where each one needs three neighbours of the species
+    /// that beats it to be eaten, the `rgb` preset.
+    pub fn rgb() -> Cyclic {
+        Cyclic::preset("rgb").unwrap()
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    pub fn range(&self) -> u8 {
+        self.range
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Replaces the generated rainbow. Panics unless there is one colour per
+    /// state.
+    pub fn with_palette(mut self, palette: Vec<Color>) -> Cyclic {
+        assert_eq!(palette.len(), self.states as usize);
+        self.palette = palette;
+        self
+    }
+
+    /// Panics if `state` is not below `states`.
+    pub fn cell(&self, state: u8) -> CyclicCell {
+        CyclicCell {
+            state,
+            color: self.palette[state as usize],
+        }
+    }
+
+    pub fn random_cell(&self, rng: &mut impl Rng) -> CyclicCell {
+        self.cell(rng.gen_range(0..self.states))
+    }
+
+    pub fn random_data(&self, size: (u16, u16), rng: &mut impl Rng) -> Vec<CyclicCell> {
+        (0..size.0 as usize * size.1 as usize)
+            .map(|_| self.random_cell(rng))
+            .collect()
+    }
+}
+
+/// Fully saturated colours evenly spaced around the hue circle.
+fn rainbow(state: u8, states: u8) -> Color {
+    let hue = state as f32 / states as f32 * 6.0;
+    let f = hue.fract();
+    let (r, g, b) = match hue as u8 {
+        0 => (1.0, f, 0.0),
+        1 => (1.0 - f, 1.0, 0.0),
+        2 => (0.0, 1.0, f),
+        3 => (0.0, 1.0 - f, 1.0),
+        4 => (f, 0.0, 1.0),
+        _ => (1.0, 0.0, 1.0 - f),
+    };
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255)
+}
+
+impl RuleSet for Cyclic {
+    type Data = CyclicCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn source_size(&self) -> u8 {
+        2 * self.range + 1
+    }
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let me = source[source.len() / 2];
+        let successor = (me.state + 1) % self.states;
+        let eaters = self
+            .neighbours
+            .iter()
+            .filter(|&&i| source[i].state == successor)
+            .count();
+        if eaters >= self.threshold as usize {
+            self.cell(successor)
+        } else {
+            me.clone()
+        }
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        (0..self.states).map(|s| self.cell(s)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Next state of the centre of a 3x3 window given in raster order.
+    fn next(rule: &Cyclic, states: [u8; 9]) -> u8 {
+        let window: Vec<CyclicCell> = states.iter().map(|&s| rule.cell(s)).collect();
+        rule.next(&window.iter().collect::<Vec<_>>()).state()
+    }
+
+    #[test]
+    fn presets_parse() {
+        for (name, rule) in CYCLIC_PRESETS {
+            assert!(Cyclic::parse(rule).is_ok(), "{}", name);
+        }
+        let rgb = Cyclic::preset("rgb").unwrap();
+        assert_eq!((rgb.states(), rgb.threshold(), rgb.range()), (3, 3, 1));
+        assert_eq!(rgb.neighborhood(), Neighborhood::Moore);
+    }
+
+    #[test]
+    fn advances_at_the_threshold() {
+        let rgb = Cyclic::rgb();
+        assert_eq!(next(&rgb, [1, 1, 0, 0, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(next(&rgb, [1, 1, 0, 0, 0, 0, 0, 0, 1]), 1);
+        assert_eq!(next(&rgb, [1, 1, 1, 1, 0, 1, 1, 1, 1]), 1);
+        // Only the state that beats the cell counts.
+        assert_eq!(next(&rgb, [1, 1, 2, 2, 0, 2, 2, 2, 2]), 0);
+
+        // The corners are not von Neumann neighbours.
+        let diamond = Cyclic::parse("R1/T2/C3/NN").unwrap();
+        assert_eq!(next(&diamond, [1, 0, 1, 0, 0, 0, 1, 0, 1]), 0);
+        assert_eq!(next(&diamond, [0, 1, 0, 1, 0, 0, 0, 0, 0]), 1);
+    }
+}
@@ -0,0 +1,176 @@
+use rand::Rng;
+
+use crate::{GError, GResult, Grid, LifeCell, RuleSet};
+
+/// One-dimensional automaton in Wolfram's numbering, shown as a space-time
+/// diagram: every row of the grid is the successor of the row above it and
+/// each generation scrolls the diagram up by one row.
+#[derive(Clone)]
+pub struct Elementary {
+    rule: u128,
+    radius: u8,
+}
+
+impl Default for Elementary {
+    fn default() -> Self {
+        Elementary::new(30, 1).unwrap()
+    }
+}
+
+impl Elementary {
+    /// `radius` 1 gives the 256 elementary rules, radius 2 and 3 the
+    /// 2^32 and 2^128 rules over five and seven cells.
+    pub fn new(rule: u128, radius: u8) -> GResult<Elementary> {
+        if !(1..=3).contains(&radius) {
+            return Err(GError::RuleParameterError(
+                "1D radius must be between 1 and 3".to_string(),
+            ));
+        }
+        let configurations = 1u32 << (2 * radius + 1);
+        if configurations < 128 && rule >> configurations != 0 {
+            return Err(GError::RuleParameterError(format!(
+                "rule {} does not exist for radius {}",
+                rule, radius
+            )));
+        }
+        Ok(Elementary { rule, radius })
+    }
+
+    pub fn rule(&self) -> u128 {
+        self.rule
+    }
+
+    pub fn radius(&self) -> u8 {
+        self.radius
+    }
+
+    fn apply(&self, neighbourhood: impl Iterator<Item = bool>) -> bool {
+        let index = neighbourhood.fold(0, |acc, alive| acc << 1 | alive as u32);
+        self.rule >> index & 1 == 1
+    }
+
+    /// Successor of a whole row, wrapping around at the ends.
+    pub fn next_row(&self, row: &[bool]) -> Vec<bool> {
+        let n = row.len() as i32;
+        let r = self.radius as i32;
+        (0..n)
+            .map(|x| self.apply((x - r..=x + r).map(|i| row[i.rem_euclid(n) as usize])))
+            .collect()
+    }
+
+    /// A complete diagram of `generations` rows starting from `first`,
+    /// ready for `Game::init_with_rules`.
+    pub fn space_time(&self, first: Vec<bool>, generations: u16) -> Vec<LifeCell> {
+        let mut data = Vec::with_capacity(first.len() * generations as usize);
+        let mut row = first;
+        for _ in 0..generations {
+            data.extend(row.iter().map(|&alive| LifeCell::from(alive)));
+            row = self.next_row(&row);
+        }
+        data
+    }
+
+    /// A row with only its middle cell alive.
+    pub fn single_seed(width: u16) -> Vec<bool> {
+        let mut row = vec![false; width as usize];
+        row[width as usize / 2] = true;
+        row
+    }
+
+    pub fn random_row(width: u16, rng: &mut impl Rng) -> Vec<bool> {
+        (0..width).map(|_| rng.gen()).collect()
+    }
+}
+
+impl RuleSet for Elementary {
+    type Data = LifeCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn source_size(&self) -> u8 {
+        2 * self.radius + 1
+    }
+
+    /// Computes the cell from the row just above it.
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let size = self.source_size() as usize;
+        let above = (self.radius as usize - 1) * size;
+        LifeCell::from(self.apply(source[above..above + size].iter().map(|c| c.is_alive())))
+    }
+
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        let width = grid.get_size().width as usize;
+        let data = grid.get_raw_data();
+        let last: Vec<bool> = data[data.len() - width..]
+            .iter()
+            .map(|c| c.is_alive())
+            .collect();
+        let mut next = Vec::with_capacity(data.len());
+        next.extend_from_slice(&data[width..]);
+        next.extend(self.next_row(&last).into_iter().map(LifeCell::from));
+        Some(next)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![LifeCell::from(false), LifeCell::from(true)]
+    }
+
+    fn supports_cell_updates(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagram(rule: u128, generations: u16) -> Vec<String> {
+        let cells = Elementary::new(rule, 1)
+            .unwrap()
+            .space_time(Elementary::single_seed(11), generations);
+        cells
+            .chunks(11)
+            .map(|row| {
+                row.iter()
+                    .map(|c| if c.is_alive() { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rule_30_from_a_single_cell() {
+        let expected = [
+            ".....#.....",
+            "....###....",
+            "...##..#...",
+            "..##.####..",
+            ".##..#...#.",
+        ];
+        assert_eq!(diagram(30, 5), expected);
+    }
+
+    #[test]
+    fn rule_90_draws_the_sierpinski_triangle() {
+        let expected = [
+            ".....#.....",
+            "....#.#....",
+            "...#...#...",
+            "..#.#.#.#..",
+            ".#.......#.",
+        ];
+        assert_eq!(diagram(90, 5), expected);
+    }
+
+    #[test]
+    fn rule_numbers_must_fit_the_radius() {
+        assert!(Elementary::new(255, 1).is_ok());
+        assert!(Elementary::new(256, 1).is_err());
+        assert!(Elementary::new((1 << 32) - 1, 2).is_ok());
+        assert!(Elementary::new(1 << 32, 2).is_err());
+        assert!(Elementary::new(30, 4).is_err());
+    }
+}
@@ -1,9 +1,30 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum GError {
     #[error("original data (size: {size}), cannot be {width} wide.")]
     InitializationError { size: usize, width: u16 },
+    #[error("cannot read {path}")]
+    FileError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("rule parse error on line {line}: {message}")]
+    RuleParseError { line: usize, message: String },
+    #[error("invalid rule string '{rule}': {message}")]
+    RuleStringError { rule: String, message: String },
+    #[error("invalid rule parameter: {0}")]
+    RuleParameterError(String),
+    #[error("invalid topology: {0}")]
+    TopologyError(String),
+    #[error("invalid schedule: {0}")]
+    ScheduleError(String),
+    #[error("invalid pattern: {0}")]
+    PatternError(String),
+    #[error("key binding error on line {line}: {message}")]
+    BindingError { line: usize, message: String },
     #[cfg(feature = "graphics-ggez")]
     #[error("Something bad happened in Ggez")]
     GgezError(#[from] ggez::GameError),
@@ -22,6 +43,9 @@ pub enum GError {
         #[from]
         source: Box<EvalAltResult>,
     },
+    #[cfg(feature = "export-png")]
+    #[error("Something went wrong writing the image")]
+    ImageError(#[from] image::ImageError),
     #[cfg(feature = "graphics-terminal")]
     #[error("Something went wrong in terminal")]
     TerminalError(#[from] std::io::Error),
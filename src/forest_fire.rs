@@ -0,0 +1,145 @@
+use rand::Rng;
+
+use crate::{
+    CellRng, Color, ColoredDataType, DataType, GError, GResult, PrintableDataType, RandomInit,
+    RuleSet,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ForestCell {
+    Empty,
+    Tree,
+    Burning,
+}
+
+impl DataType for ForestCell {}
+
+impl ColoredDataType for ForestCell {
+    fn get_color(&self) -> Color {
+        match self {
+            ForestCell::Empty => (40, 25, 10, 255),
+            ForestCell::Tree => (20, 140, 30, 255),
+            ForestCell::Burning => (255, 120, 0, 255),
+        }
+    }
+}
+
+impl PrintableDataType for ForestCell {
+    fn get_char(&self) -> char {
+        match self {
+            ForestCell::Empty => ' ',
+            ForestCell::Tree => 'T',
+            ForestCell::Burning => '#',
+        }
+    }
+}
+
+impl RandomInit for ForestCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        if rng.gen_bool(0.5) {
+            ForestCell::Tree
+        } else {
+            ForestCell::Empty
+        }
+    }
+}
+
+/// The Drossel-Schwabl forest-fire model: burning trees burn down, trees
+/// next to a fire catch it, other trees are struck by lightning with
+/// probability `lightning` and empty cells grow a tree with probability
+/// `growth`. Fire spreads to the four orthogonal neighbours.
+#[derive(Clone)]
+pub struct ForestFire {
+    growth: f64,
+    lightning: f64,
+}
+
+impl Default for ForestFire {
+    fn default() -> Self {
+        ForestFire::new(0.01, 0.00001).unwrap()
+    }
+}
+
+impl ForestFire {
+    pub fn new(growth: f64, lightning: f64) -> GResult<ForestFire> {
+        if !(0.0..=1.0).contains(&growth) || !(0.0..=1.0).contains(&lightning) {
+            return Err(GError::RuleParameterError(format!(
+                "growth {} and lightning {} must be probabilities",
+                growth, lightning
+            )));
+        }
+        Ok(ForestFire { growth, lightning })
+    }
+
+    pub fn growth(&self) -> f64 {
+        self.growth
+    }
+
+    pub fn lightning(&self) -> f64 {
+        self.lightning
+    }
+}
+
+impl RuleSet for ForestFire {
+    type Data = ForestCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    /// Without randomness nothing grows and lightning never strikes, so
+    /// only existing fires spread.
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let fire_nearby = [1, 3, 5, 7]
+            .iter()
+            .any(|&i| *source[i] == ForestCell::Burning);
+        match source[4] {
+            ForestCell::Tree if fire_nearby => ForestCell::Burning,
+            ForestCell::Burning => ForestCell::Empty,
+            &cell => cell,
+        }
+    }
+
+    fn next_with_rng(&self, source: &[&Self::Data], rng: &mut CellRng) -> Self::Data {
+        match self.next(source) {
+            ForestCell::Empty if *source[4] == ForestCell::Empty && rng.gen_bool(self.growth) => {
+                ForestCell::Tree
+            }
+            ForestCell::Tree if rng.gen_bool(self.lightning) => ForestCell::Burning,
+            cell => cell,
+        }
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![ForestCell::Empty, ForestCell::Tree, ForestCell::Burning]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn burn(seed: u64, threads: u16) -> Vec<ForestCell> {
+        let rules = ForestFire::new(0.05, 0.001).unwrap();
+        let mut game = Game::init_generated_data(rules, (32, 24), seed, |_| ForestCell::Empty)
+            .unwrap()
+            .with_threads(threads);
+        for _ in 0..60 {
+            game.next_step();
+        }
+        (0..24)
+            .flat_map(|y| (0..32).map(move |x| (x, y)))
+            .map(|c| *game.get_view_cell(c))
+            .collect()
+    }
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let first = burn(5, 4);
+        assert!(first.contains(&ForestCell::Tree));
+        assert!(first.contains(&ForestCell::Burning));
+        assert_eq!(first, burn(5, 4));
+        assert_ne!(first, burn(6, 4));
+        for threads in [1, 3, 7] {
+            assert_eq!(first, burn(5, threads), "{} threads", threads);
+        }
+    }
+}
@@ -1,9 +1,20 @@
-use std::{ops::Index, sync::Arc, thread};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Index,
+    path::Path,
+    sync::Arc,
+    thread,
+};
+
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 
 use crate::{
     backends::{Backend, BackendStatic},
     grid::Grid,
-    ColoredDataType, DataType, GResult, IndexType, PrintableDataType, RandomInit, RuleSet, Size,
+    Agent, Bindings, CellRng, Color, ColoredDataType, DataType, GError, GResult, IndexType,
+    Pattern, PrintableDataType, RandomInit, RuleSet, Schedule, Size, Topology,
 };
 
 pub struct Game<R>
@@ -11,14 +22,256 @@ where
     R: RuleSet,
 {
     grid: Grid<R::Data>,
+    rules: R,
+    agents: Vec<Box<dyn Agent<R::Data>>>,
+    seed: u64,
+    generation: u64,
+    slice: u16,
+    schedule: Schedule,
+    layer: Option<usize>,
+    brush: usize,
+    bindings: Bindings,
+    /// Grids of the last generations, newest last, for `step_back`.
+    history: VecDeque<Vec<R::Data>>,
+    history_len: usize,
+    threads: u16,
+    /// The grid as it was at generation 0, for `reset`.
+    start: Option<Vec<R::Data>>,
 }
 
+/// Generations `step_back` can undo unless set with `Game::with_history`.
+const DEFAULT_HISTORY: usize = 64;
+/// Threads sharing a synchronous step unless set with `Game::with_threads`.
+const DEFAULT_THREADS: u16 = 4;
+
 impl<R> Game<R>
 where
     R: RuleSet,
 {
-    pub fn init_with_data(init_data: Vec<R::Data>, width: u16) -> GResult<Game<R>> {
-        Grid::init_with_data(init_data, width).map(|grid| Game { grid })
+    pub fn init_with_rules(rules: R, init_data: Vec<R::Data>, width: u16) -> GResult<Game<R>> {
+        Grid::init_with_data(init_data, width).map(|grid| Game {
+            grid,
+            rules,
+            agents: Vec::new(),
+            seed: rand::random(),
+            generation: 0,
+            slice: 0,
+            schedule: Schedule::Synchronous,
+            layer: None,
+            brush: 1,
+            bindings: Bindings::default(),
+            history: VecDeque::new(),
+            history_len: DEFAULT_HISTORY,
+            threads: DEFAULT_THREADS,
+            start: None,
+        })
+    }
+
+    /// Fills a grid of `game_size` with cells made by `generate` from a
+    /// generator seeded with `seed`. The game keeps the seed for
+    /// `RuleSet::next_with_rng`, so the whole run can be replayed from it.
+    pub fn init_generated_data<F>(
+        rules: R,
+        game_size: (u16, u16),
+        seed: u64,
+        mut generate: F,
+    ) -> GResult<Game<R>>
+    where
+        F: FnMut(&mut StdRng) -> R::Data,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let total_size = game_size.0 as usize * game_size.1 as usize;
+        let data = (0..total_size).map(|_| generate(&mut rng)).collect();
+        let mut game = Game::init_with_rules(rules, data, game_size.0)?;
+        game.seed = seed;
+        Ok(game)
+    }
+
+    pub fn with_topology<T: Topology>(mut self, topology: T) -> GResult<Game<R>> {
+        self.rules.check_topology(&topology)?;
+        self.grid = self.grid.with_topology(topology)?;
+        self.slice = 0;
+        Ok(self)
+    }
+
+    pub fn get_topology(&self) -> &dyn Topology {
+        self.grid.get_topology()
+    }
+
+    pub fn with_schedule(mut self, schedule: Schedule) -> GResult<Game<R>> {
+        schedule.check(&self.rules)?;
+        self.schedule = schedule;
+        Ok(self)
+    }
+
+    pub fn get_schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    /// Keys the backends map onto actions.
+    pub fn with_bindings(mut self, bindings: Bindings) -> Game<R> {
+        self.bindings = bindings;
+        self
+    }
+
+    pub fn get_bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// How many generations `step_back` can undo. Each one keeps a copy of
+    /// the grid, so large grids may want fewer.
+    pub fn with_history(mut self, generations: usize) -> Game<R> {
+        self.history_len = generations;
+        self.history.truncate(generations);
+        self
+    }
+
+    /// How many threads share the cells of a synchronous step, each taking
+    /// a band of rows. The results do not depend on it.
+    pub fn with_threads(mut self, threads: u16) -> Game<R> {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Chooses the slice the backends show when the topology has several,
+    /// wrapping around past either end.
+    pub fn set_slice(&mut self, slice: i32) {
+        let slices = self.grid.get_topology().slices() as i32;
+        self.slice = slice.rem_euclid(slices) as u16;
+    }
+
+    pub fn get_slice(&self) -> u16 {
+        self.slice
+    }
+
+    /// Size of one slice, which is the part of the grid backends draw.
+    pub fn get_view_size(&self) -> Size {
+        let size = self.grid.get_size();
+        Size {
+            width: size.width,
+            height: size.height / self.grid.get_topology().slices(),
+        }
+    }
+
+    fn view_offset(&self) -> i32 {
+        self.slice as i32 * self.get_view_size().height as i32
+    }
+
+    /// Position in the grid of `index` within the slice shown, wrapping
+    /// around the edges of the slice.
+    pub fn view_index(&self, index: IndexType) -> IndexType {
+        let size = self.get_view_size();
+        let x = index.0.rem_euclid(size.width as i32);
+        let y = index.1.rem_euclid(size.height as i32);
+        (x, y + self.view_offset())
+    }
+
+    /// The cell at `index` of the slice shown.
+    pub fn get_view_cell(&self, index: IndexType) -> &R::Data {
+        &self.grid[self.view_index(index)]
+    }
+
+    /// The cell shown at point `(x, y)` of `to_image`, if any.
+    pub fn pick(&self, x: f32, y: f32) -> Option<IndexType> {
+        let topology = self.grid.get_topology();
+        let (column, row) = topology.pick(x, y, self.get_view_size())?;
+        Some((column, row + self.view_offset()))
+    }
+
+    /// Chooses the state of `RuleSet::palette` that `paint` puts down.
+    /// States past the end of the palette are ignored.
+    pub fn set_brush(&mut self, state: usize) {
+        if state < self.rules.palette().len() {
+            self.brush = state;
+        }
+    }
+
+    pub fn get_brush(&self) -> usize {
+        self.brush
+    }
+
+    pub fn paint(&mut self, index: IndexType) {
+        if let Some(cell) = self.rules.palette().into_iter().nth(self.brush) {
+            self.grid[index] = cell;
+        }
+    }
+
+    /// Puts down the first state of the palette.
+    pub fn erase(&mut self, index: IndexType) {
+        if let Some(cell) = self.rules.palette().into_iter().next() {
+            self.grid[index] = cell;
+        }
+    }
+
+    /// Copies `pattern` into the slice shown with its top-left corner at
+    /// `at`, wrapping around the edges. Pattern states pick cells from
+    /// `RuleSet::palette`.
+    pub fn stamp(&mut self, pattern: &Pattern, at: IndexType) -> GResult<()> {
+        let palette = self.rules.palette();
+        if pattern.max_state() as usize >= palette.len() {
+            return Err(GError::PatternError(format!(
+                "state {} is not in the palette of {} states",
+                pattern.max_state(),
+                palette.len()
+            )));
+        }
+        for y in 0..pattern.height() {
+            for x in 0..pattern.width() {
+                let index = self.view_index((at.0 + x as i32, at.1 + y as i32));
+                self.grid[index] = palette[pattern.state(x, y) as usize].clone();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        if let Some(cell) = self.rules.palette().into_iter().next() {
+            self.grid.get_raw_mut_data().fill(cell);
+        }
+    }
+
+    /// Fills the grid with states of the palette picked at random from
+    /// `seed`, which becomes the game seed, and starts counting generations
+    /// again.
+    pub fn randomize(&mut self, seed: u64) {
+        let palette = self.rules.palette();
+        if palette.is_empty() {
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        for cell in self.grid.get_raw_mut_data() {
+            *cell = palette[rng.gen_range(0..palette.len())].clone();
+        }
+        self.seed = seed;
+        self.generation = 0;
+        self.history.clear();
+    }
+
+    pub fn get_rules(&self) -> &R {
+        &self.rules
+    }
+
+    pub fn add_agent<A: Agent<R::Data>>(&mut self, agent: A) {
+        self.agents.push(Box::new(agent));
+    }
+
+    pub fn get_agents(&self) -> &[Box<dyn Agent<R::Data>>] {
+        &self.agents
+    }
+
+    /// Seed of the random streams passed to `RuleSet::next_with_rng`. A new
+    /// game picks one at random; setting it makes runs reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of generations computed so far.
+    pub fn get_generation(&self) -> u64 {
+        self.generation
     }
 
     fn get_coord_iter(&self) -> CoordIter {
@@ -30,20 +283,123 @@ where
             y: 0,
         }
     }
+    /// Updates every cell with the rules and then moves the agents, in the
+    /// order they were added.
     pub fn next_step(&mut self) {
-        const NUMBER_OF_THREADS: u16 = 4;
+        if self.generation == 0 {
+            self.start = Some(self.grid.get_raw_data().to_vec());
+        }
+        if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.grid.get_raw_data().to_vec());
+        }
+        self.step_cells();
+        for agent in self.agents.iter_mut() {
+            agent.step(&mut self.grid);
+        }
+        self.generation += 1;
+    }
+
+    /// Puts back the grid of the previous generation, if it is still in the
+    /// history. Agents are not moved back. Returns whether it went back.
+    pub fn step_back(&mut self) -> bool {
+        let Some(previous) = self.history.pop_back() else {
+            return false;
+        };
+        self.grid.get_raw_mut_data().clone_from_slice(&previous);
+        self.generation = self.generation.saturating_sub(1);
+        true
+    }
+
+    /// Goes back to the grid of generation 0, including any edits made
+    /// before the first step. Agents are not moved back.
+    pub fn reset(&mut self) {
+        if self.generation == 0 {
+            return;
+        }
+        if let Some(start) = &self.start {
+            self.grid.get_raw_mut_data().clone_from_slice(start);
+        }
+        self.generation = 0;
+        self.history.clear();
+    }
+
+    fn step_cells(&mut self) {
+        let mut rng = CellRng::for_generation(self.seed, self.generation);
+        let size = self.grid.get_size();
+        match self.schedule {
+            Schedule::Synchronous => self.step_synchronous(),
+            Schedule::Asynchronous(p) => {
+                let previous = self.grid.get_raw_data().to_vec();
+                self.step_synchronous();
+                for (cell, old) in self.grid.get_raw_mut_data().iter_mut().zip(previous) {
+                    if !rng.gen_bool(p) {
+                        *cell = old;
+                    }
+                }
+            }
+            Schedule::RandomSequential => {
+                for _ in 0..size.width as usize * size.height as usize {
+                    let c = (
+                        rng.gen_range(0..size.width as i32),
+                        rng.gen_range(0..size.height as i32),
+                    );
+                    // A cell may come up more than once, so its stream is
+                    // drawn from the generation's one.
+                    let mut cell_rng = CellRng::new(rng.next_u64(), self.generation, c);
+                    self.grid[c] = self.next_cell(c, &mut cell_rng);
+                }
+            }
+            Schedule::Checkerboard => {
+                for parity in 0..2 {
+                    let updates: Vec<_> = self
+                        .get_coord_iter()
+                        .filter(|c| (c.0 + c.1) % 2 == parity)
+                        .map(|c| (c, self.next_cell(c, &mut self.cell_rng(c))))
+                        .collect();
+                    for (c, cell) in updates {
+                        self.grid[c] = cell;
+                    }
+                }
+            }
+            Schedule::Ordered => {
+                for c in self.get_coord_iter() {
+                    self.grid[c] = self.next_cell(c, &mut self.cell_rng(c));
+                }
+            }
+        }
+    }
+
+    fn cell_rng(&self, c: IndexType) -> CellRng {
+        CellRng::new(self.seed, self.generation, c)
+    }
+
+    fn next_cell(&self, c: IndexType, rng: &mut CellRng) -> R::Data {
+        let area = self.grid.get_area(c, self.rules.source_size());
+        self.rules.next_with_rng(area.as_slice(), rng)
+    }
+
+    fn step_synchronous(&mut self) {
+        if let Some(data) = self.rules.next_generation(&self.grid, self.generation) {
+            self.grid.get_raw_mut_data().clone_from_slice(&data);
+            return;
+        }
         let grid_copy = Arc::new(self.grid.clone());
-        //let rules_copy = Arc::new(self.rules.clone());
+        let rules_copy = Arc::new(self.rules.clone());
         let mut handles = vec![];
         let size = self.grid.get_size();
         let height = size.height;
         let width = size.width;
-        let source_size = R::SOURCE_SIZE;
-        for index in 0..NUMBER_OF_THREADS {
-            let y_start = index * height / NUMBER_OF_THREADS;
-            let y_end = (index + 1) * height / NUMBER_OF_THREADS;
+        let source_size = self.rules.source_size();
+        let (seed, generation) = (self.seed, self.generation);
+        let threads = self.threads;
+        for index in 0..threads {
+            let y_start = (index as u32 * height as u32 / threads as u32) as u16;
+            let y_end = ((index as u32 + 1) * height as u32 / threads as u32) as u16;
             let grid_copy = Arc::clone(&grid_copy);
-            //let rules_copy = Arc::clone(&rules_copy);
+            let rules_copy = Arc::clone(&rules_copy);
             let handle = thread::spawn(move || {
                 let iter = CoordIter {
                     width,
@@ -54,7 +410,8 @@ where
                 let mut v = Vec::with_capacity(((y_end - y_start) * width) as usize);
                 for c in iter {
                     let area = grid_copy.get_area(c, source_size);
-                    v.push(R::next(area.as_slice()));
+                    let mut rng = CellRng::new(seed, generation, c);
+                    v.push(rules_copy.next_with_rng(area.as_slice(), &mut rng));
                 }
                 (y_start, y_end, v)
             });
@@ -74,25 +431,45 @@ where
     }
 }
 
-/*impl<R> Game<R>
-    where R: InitRuleSet {
+impl<R> Game<R>
+where
+    R: RuleSet + Default,
+{
     pub fn init_with_data(init_data: Vec<R::Data>, width: u16) -> GResult<Game<R>> {
-        Grid::init_with_data(init_data, width).map(|grid| Game { grid, rules: R::init() })
+        Game::init_with_rules(R::default(), init_data, width)
     }
-}*/
+}
 
 impl<R> Game<R>
 where
     R: RuleSet,
     R::Data: RandomInit,
+{
+    pub fn init_random_data_with_rules(rules: R, game_size: (u16, u16)) -> GResult<Game<R>> {
+        Game::init_random_data_with_rules_seeded(rules, game_size, rand::random())
+    }
+
+    pub fn init_random_data_with_rules_seeded(
+        rules: R,
+        game_size: (u16, u16),
+        seed: u64,
+    ) -> GResult<Game<R>> {
+        Game::init_generated_data(rules, game_size, seed, R::Data::rnd)
+    }
+}
+
+impl<R> Game<R>
+where
+    R: RuleSet + Default,
+    R::Data: RandomInit,
 {
     pub fn init_random_data(game_size: (u16, u16)) -> GResult<Game<R>> {
-        let total_size = game_size.0 as usize * game_size.1 as usize;
-        let mut data = Vec::with_capacity(total_size);
-        for _ in 0..total_size {
-            data.push(R::Data::rnd())
-        }
-        Game::init_with_data(data, game_size.0)
+        Game::init_random_data_with_rules(R::default(), game_size)
+    }
+
+    /// The same soup, and the same run, for the same seed.
+    pub fn init_random_data_seeded(game_size: (u16, u16), seed: u64) -> GResult<Game<R>> {
+        Game::init_random_data_with_rules_seeded(R::default(), game_size, seed)
     }
 }
 
@@ -111,19 +488,169 @@ where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
+    /// Names of the layers `set_layer` can pick, from the top, as the
+    /// first state of the palette or else the first cell gives them.
+    pub fn get_layer_names(&self) -> Vec<&'static str> {
+        self.rules
+            .palette()
+            .first()
+            .or(self.grid.get_raw_data().first())
+            .map_or_else(Vec::new, ColoredDataType::layer_names)
+    }
+
+    /// Shows only the given layer of `Layered` cells, or all of them blended
+    /// with `None`. Layers past the last one are ignored.
+    pub fn set_layer(&mut self, layer: Option<usize>) {
+        if layer.is_none_or(|l| l < R::Data::layer_count()) {
+            self.layer = layer;
+        }
+    }
+
+    pub fn get_layer(&self) -> Option<usize> {
+        self.layer
+    }
+
+    /// Goes from the blend to each layer in turn and back to the blend.
+    pub fn next_layer(&mut self) {
+        self.layer = match self.layer {
+            None if R::Data::layer_count() > 1 => Some(0),
+            Some(l) if l + 1 < R::Data::layer_count() => Some(l + 1),
+            _ => None,
+        };
+    }
+
+    fn cell_color(&self, cell: &R::Data) -> Color {
+        match self.layer {
+            Some(layer) => cell.get_layer_color(layer),
+            None => cell.get_color(),
+        }
+    }
+
+    /// Colour of the cell at `index`, or of the last agent standing on it.
+    pub fn get_color(&self, index: IndexType) -> Color {
+        self.agents
+            .iter()
+            .rev()
+            .find(|a| a.position() == index)
+            .map_or_else(|| self.cell_color(&self.grid[index]), |a| a.get_color())
+    }
+
+    /// Colour at `index` within the slice being shown.
+    pub fn get_view_color(&self, index: IndexType) -> Color {
+        self.get_color((index.0, index.1 + self.view_offset()))
+    }
+
+    /// Colours of the states of `RuleSet::palette` as the view shows them.
+    pub fn get_palette_colors(&self) -> Vec<Color> {
+        let palette = self.rules.palette();
+        palette.iter().map(|c| self.cell_color(c)).collect()
+    }
+
+    /// Top-left and bottom-right cells of the smallest rectangle of the
+    /// slice shown holding every cell that does not look like the first
+    /// state of the palette, or that is not black or transparent when the
+    /// rules have no palette. `None` when there are no such cells.
+    pub fn pattern_bounds(&self) -> Option<(IndexType, IndexType)> {
+        let empty = self.rules.palette().first().map(|c| self.cell_color(c));
+        let size = self.get_view_size();
+        let mut bounds: Option<(IndexType, IndexType)> = None;
+        for y in 0..size.height as i32 {
+            for x in 0..size.width as i32 {
+                let (r, g, b, a) = self.get_view_color((x, y));
+                let is_empty = match empty {
+                    Some(empty) => (r, g, b, a) == empty,
+                    None => a == 0 || (r, g, b) == (0, 0, 0),
+                };
+                if is_empty {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    None => ((x, y), (x, y)),
+                    Some((min, max)) => {
+                        ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+                    }
+                });
+            }
+        }
+        bounds
+    }
+
+    /// Paints the cell at `index`, or erases it if it already looks like the
+    /// brush.
+    pub fn toggle(&mut self, index: IndexType) {
+        let brush = self.rules.palette().into_iter().nth(self.brush);
+        if brush.is_some_and(|b| self.cell_color(&b) == self.cell_color(&self.grid[index])) {
+            self.erase(index);
+        } else {
+            self.paint(index);
+        }
+    }
+
     pub fn to_raw_colors(&self) -> (Vec<u8>, Size) {
         let size = self.grid.get_size();
         let capacity = size.width as usize * size.height as usize * 4;
         let mut v = Vec::with_capacity(capacity);
         for (_, d) in self.into_iter() {
-            let (r, g, b, a) = d.get_color();
+            let (r, g, b, a) = self.cell_color(d);
             v.push(r);
             v.push(g);
             v.push(b);
             v.push(a);
         }
+        for agent in self.agents.iter() {
+            let (x, y) = agent.position();
+            if x < 0 || y < 0 || x >= size.width as i32 || y >= size.height as i32 {
+                continue;
+            }
+            let i = (y as usize * size.width as usize + x as usize) * 4;
+            let (r, g, b, a) = agent.get_color();
+            v[i..i + 4].copy_from_slice(&[r, g, b, a]);
+        }
         (v, size)
     }
+
+    /// The slice being shown as an RGBA picture, drawn by the topology.
+    pub fn to_image(&self) -> (Vec<u8>, Size) {
+        let (mut colors, _) = self.to_raw_colors();
+        let size = self.get_view_size();
+        let row = size.width as usize * 4;
+        let start = self.view_offset() as usize * row;
+        colors.drain(..start);
+        colors.truncate(size.height as usize * row);
+        self.grid.get_topology().draw(colors, size)
+    }
+
+    /// Writes every slice as voxels, one `x y z r g b` line per cell that is
+    /// not drawn black or transparent, which makes it easy to load into
+    /// point-cloud and voxel tools. `z` is the slice number.
+    pub fn save_voxels<P: AsRef<Path>>(&self, path: P) -> GResult<()> {
+        let path = path.as_ref();
+        let file_error = |source| GError::FileError {
+            path: path.to_path_buf(),
+            source,
+        };
+        let height = self.get_view_size().height as i32;
+        let mut out = BufWriter::new(File::create(path).map_err(file_error)?);
+        writeln!(out, "# x y z r g b").map_err(file_error)?;
+        for (index, cell) in self.into_iter() {
+            let (r, g, b, a) = self.cell_color(cell);
+            if a == 0 || (r, g, b) == (0, 0, 0) {
+                continue;
+            }
+            let (x, y, z) = (index.0, index.1 % height, index.1 / height);
+            writeln!(out, "{} {} {} {} {} {}", x, y, z, r, g, b).map_err(file_error)?;
+        }
+        out.flush().map_err(file_error)
+    }
+
+    #[cfg(feature = "export-png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> GResult<()> {
+        let (data, size) = self.to_image();
+        image::RgbaImage::from_raw(size.width as u32, size.height as u32, data)
+            .expect("colour buffer matches the grid size")
+            .save(path)?;
+        Ok(())
+    }
 }
 
 impl<R> Game<R>
@@ -132,9 +659,11 @@ where
     R::Data: DataType,
 {
     pub fn run<B: Backend<R>>(&mut self, window_size: (u32, u32)) -> GResult<()> {
+        self.rules.check_topology(self.get_topology())?;
         B::run(window_size, self).map_err(|e| e.into())
     }
     pub fn run_owned<B: BackendStatic<R>>(self, window_size: (u32, u32)) -> GResult<()> {
+        self.rules.check_topology(self.get_topology())?;
         B::run(window_size, self).map_err(|e| e.into())
     }
 }
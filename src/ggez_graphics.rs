@@ -1,19 +1,23 @@
-use ggez::conf::WindowMode;
+use ggez::conf::{WindowMode, WindowSetup};
 use ggez::event::run as ggez_run;
 use ggez::event::EventHandler;
 use ggez::graphics;
 use ggez::graphics::{Color, DrawParam, Image, ImageFormat, Rect, Sampler};
-use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::input::keyboard::{KeyInput, KeyMods};
+use ggez::input::mouse::MouseButton;
 use ggez::{Context, ContextBuilder, GameResult};
 
-use crate::{ColoredDataType, Game, RuleSet};
+use crate::{Action, ColoredDataType, Controller, Game, KeyPress, RuleSet, Viewport};
+
+const TITLE: &str = "Game of Life";
 
 pub fn run<R: 'static>(window_size: (u32, u32), game: Game<R>) -> GameResult<()>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let (ctx, event_loop) = ContextBuilder::new("Game of Life", "Eero")
+    let (ctx, event_loop) = ContextBuilder::new(TITLE, "Eero")
+        .window_setup(WindowSetup::default().title(TITLE))
         .window_mode(WindowMode {
             width: window_size.0 as f32,
             height: window_size.1 as f32,
@@ -21,7 +25,7 @@ where
         })
         .build()
         .unwrap();
-    let handler = MyEventHandler::<R>::new(game);
+    let handler = MyEventHandler::<R>::new(&ctx, game);
     ggez_run(ctx, event_loop, handler);
 }
 
@@ -31,24 +35,36 @@ where
 {
     game: Game<R>,
     fps: graphics::Text,
-    is_pause: bool,
-    show_fps: bool,
-    screen_coords: Rect,
+    controller: Controller,
+    viewport: Viewport,
 }
 
 impl<R> MyEventHandler<R>
 where
     R: RuleSet,
+    R::Data: ColoredDataType,
 {
-    fn new(game: Game<R>) -> MyEventHandler<R> {
-        let size = game.get_size();
-        let coords = Rect::new_i32(0, 0, size.width as i32, size.height as i32);
+    fn new(ctx: &Context, game: Game<R>) -> MyEventHandler<R> {
+        let (_, size) = game.to_image();
+        let viewport = Viewport::new(size, ctx.gfx.drawable_size());
+        let controller = Controller::new(game.get_bindings().clone());
+        ctx.gfx.set_window_title(&controller.title(TITLE, &game));
         MyEventHandler {
             game,
             fps: graphics::Text::new(""),
-            is_pause: true,
-            show_fps: false,
-            screen_coords: coords,
+            controller,
+            viewport,
+        }
+    }
+
+    /// Left paints with the brush and right erases. Window pixels are mapped
+    /// through the viewport to the image the grid is drawn in.
+    fn edit(&mut self, button: MouseButton, x: f32, y: f32) {
+        let (x, y) = self.viewport.to_image((x, y));
+        match (button, self.game.pick(x, y)) {
+            (MouseButton::Left, Some(cell)) => self.game.paint(cell),
+            (MouseButton::Right, Some(cell)) => self.game.erase(cell),
+            _ => (),
         }
     }
 }
@@ -59,10 +75,9 @@ where
     R::Data: ColoredDataType,
 {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        if !self.is_pause {
-            self.game.next_step();
-        }
-        if self.show_fps {
+        self.controller.wait_for_frame();
+        self.controller.update(&mut self.game);
+        if self.controller.show_fps() {
             self.fps = graphics::Text::new(format!("{:.2}", ctx.time.fps()));
         }
 
@@ -70,7 +85,7 @@ where
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let (data, size) = self.game.to_raw_colors();
+        let (data, size) = self.game.to_image();
         let img = Image::from_pixels(
             ctx,
             &data,
@@ -79,11 +94,13 @@ where
             size.height as u32,
         );
         let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
-        canvas.set_screen_coordinates(self.screen_coords);
+        let (x, y) = self.viewport.get_corner();
+        let (w, h) = self.viewport.get_visible_size();
+        canvas.set_screen_coordinates(Rect::new(x, y, w, h));
         canvas.set_sampler(Sampler::nearest_clamp());
 
         canvas.draw(&img, DrawParam::new());
-        if self.show_fps {
+        if self.controller.show_fps() {
             canvas.draw(
                 &self.fps,
                 DrawParam::new().color(Color::WHITE).dest([0.0, 0.0]),
@@ -99,17 +116,64 @@ where
         input: KeyInput,
         _repeat: bool,
     ) -> GameResult<()> {
-        match input.keycode {
-            Some(KeyCode::Space) => self.is_pause ^= true,
-            Some(KeyCode::Escape) => ctx.request_quit(),
-            Some(KeyCode::F) => self.show_fps ^= true,
-            Some(KeyCode::Right) => {
-                if self.is_pause {
-                    self.game.next_step()
-                }
-            }
-            _ => (),
+        let shift = input.mods.contains(KeyMods::SHIFT);
+        let Some(press) = input.keycode.and_then(|k| KeyPress::from_code(&k, shift)) else {
+            return Ok(());
         };
+        match self
+            .controller
+            .press(press, &mut self.game, &mut self.viewport)
+        {
+            Some(Action::Quit) => ctx.request_quit(),
+            Some(_) => {
+                let title = self.controller.title(TITLE, &self.game);
+                ctx.gfx.set_window_title(&title);
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        self.edit(button, x, y);
+        Ok(())
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult<()> {
+        for button in [MouseButton::Left, MouseButton::Right] {
+            if ctx.mouse.button_pressed(button) {
+                self.edit(button, x, y);
+            }
+        }
+        // The middle button drags the view.
+        if ctx.mouse.button_pressed(MouseButton::Middle) {
+            self.viewport.pan(dx, dy);
+        }
+        Ok(())
+    }
+
+    /// The wheel zooms around the pointer.
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult<()> {
+        let pointer = ctx.mouse.position();
+        let anchor = (pointer.x, pointer.y);
+        if y > 0.0 {
+            self.viewport.zoom_in(anchor);
+        } else if y < 0.0 {
+            self.viewport.zoom_out(anchor);
+        }
         Ok(())
     }
 }
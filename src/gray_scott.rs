@@ -0,0 +1,305 @@
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, GError, GResult, Grid, RandomInit, RuleSet};
+
+/// 3x3 Laplacian weights in the layout of the source window.
+const LAPLACIAN: [f32; 9] = [0.05, 0.2, 0.05, 0.2, -1.0, 0.2, 0.05, 0.2, 0.05];
+
+#[derive(Clone)]
+pub struct ChemicalData {
+    u: f32,
+    v: f32,
+}
+
+impl ChemicalData {
+    pub fn new(u: f32, v: f32) -> ChemicalData {
+        ChemicalData {
+            u: u.clamp(0.0, 1.0),
+            v: v.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn u(&self) -> f32 {
+        self.u
+    }
+
+    pub fn v(&self) -> f32 {
+        self.v
+    }
+}
+
+impl DataType for ChemicalData {}
+
+impl ColoredDataType for ChemicalData {
+    fn get_color(&self) -> Color {
+        let t = (self.v * 2.5).min(1.0);
+        if t < 0.5 {
+            let s = t * 2.0;
+            (
+                (s * 40.0) as u8,
+                (s * 120.0) as u8,
+                (60.0 + s * 140.0) as u8,
+                255,
+            )
+        } else {
+            let s = t * 2.0 - 1.0;
+            (
+                (40.0 + s * 215.0) as u8,
+                (120.0 + s * 135.0) as u8,
+                (200.0 + s * 55.0) as u8,
+                255,
+            )
+        }
+    }
+}
+
+impl RandomInit for ChemicalData {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        if rng.gen_bool(0.05) {
+            ChemicalData::new(0.5, 0.25 + rng.gen::<f32>() * 0.05)
+        } else {
+            ChemicalData::new(1.0, 0.0)
+        }
+    }
+}
+
+/// Gray-Scott reaction-diffusion: `u + 2v -> 3v` with `u` fed in at `feed`
+/// and `v` removed at `feed + kill`. Each generation runs `steps` explicit
+/// Euler steps.
+#[derive(Clone)]
+pub struct GrayScott {
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    time_step: f32,
+    steps: u16,
+}
+
+impl Default for GrayScott {
+    fn default() -> Self {
+        GrayScott::coral()
+    }
+}
+
+impl GrayScott {
+    pub fn new(feed: f32, kill: f32) -> GResult<GrayScott> {
+        if !(0.0..=0.1).contains(&feed) || !(0.0..=0.1).contains(&kill) {
+            return Err(GError::RuleParameterError(format!(
+                "feed {} and kill {} must be between 0 and 0.1",
+                feed, kill
+            )));
+        }
+        Ok(GrayScott {
+            feed,
+            kill,
+            diffusion_u: 1.0,
+            diffusion_v: 0.5,
+            time_step: 1.0,
+            steps: 8,
+        })
+    }
+
+    /// The explicit scheme is only stable while `time_step * diffusion`
+    /// stays below 1.25 for both chemicals.
+    pub fn with_diffusion(mut self, diffusion_u: f32, diffusion_v: f32) -> GResult<GrayScott> {
+        if !self.is_stable(diffusion_u, diffusion_v, self.time_step) {
+            return Err(GError::RuleParameterError(
+                "diffusion is too large for the time step".to_string(),
+            ));
+        }
+        self.diffusion_u = diffusion_u;
+        self.diffusion_v = diffusion_v;
+        Ok(self)
+    }
+
+    pub fn with_time_step(mut self, time_step: f32) -> GResult<GrayScott> {
+        if time_step <= 0.0 || !self.is_stable(self.diffusion_u, self.diffusion_v, time_step) {
+            return Err(GError::RuleParameterError(
+                "time step is too large for the diffusion".to_string(),
+            ));
+        }
+        self.time_step = time_step;
+        Ok(self)
+    }
+
+    pub fn with_steps(mut self, steps: u16) -> GrayScott {
+        self.steps = steps.max(1);
+        self
+    }
+
+    pub fn coral() -> GrayScott {
+        GrayScott::new(0.0545, 0.062).unwrap()
+    }
+
+    pub fn mitosis() -> GrayScott {
+        GrayScott::new(0.0367, 0.0649).unwrap()
+    }
+
+    pub fn solitons() -> GrayScott {
+        GrayScott::new(0.03, 0.062).unwrap()
+    }
+
+    pub fn worms() -> GrayScott {
+        GrayScott::new(0.078, 0.061).unwrap()
+    }
+
+    fn is_stable(&self, diffusion_u: f32, diffusion_v: f32, time_step: f32) -> bool {
+        diffusion_u >= 0.0 && diffusion_v >= 0.0 && diffusion_u.max(diffusion_v) * time_step < 1.25
+    }
+
+    fn react(&self, me: &ChemicalData, laplace_u: f32, laplace_v: f32) -> ChemicalData {
+        let uvv = me.u * me.v * me.v;
+        let du = self.diffusion_u * laplace_u - uvv + self.feed * (1.0 - me.u);
+        let dv = self.diffusion_v * laplace_v + uvv - (self.feed + self.kill) * me.v;
+        ChemicalData::new(me.u + du * self.time_step, me.v + dv * self.time_step)
+    }
+
+    /// A field of pure `u` with a square of `v` of half-width `radius`,
+    /// slightly noisy so that the pattern can break its symmetry.
+    pub fn seed_square(
+        size: (u16, u16),
+        center: (u16, u16),
+        radius: u16,
+        rng: &mut impl Rng,
+    ) -> Vec<ChemicalData> {
+        let mut data = vec![ChemicalData::new(1.0, 0.0); size.0 as usize * size.1 as usize];
+        GrayScott::add_spot(&mut data, size, center, radius, rng);
+        data
+    }
+
+    /// A field of pure `u` with `count` randomly placed squares of `v`.
+    pub fn seed_spots(
+        size: (u16, u16),
+        count: usize,
+        radius: u16,
+        rng: &mut impl Rng,
+    ) -> Vec<ChemicalData> {
+        let mut data = vec![ChemicalData::new(1.0, 0.0); size.0 as usize * size.1 as usize];
+        for _ in 0..count {
+            let center = (rng.gen_range(0..size.0), rng.gen_range(0..size.1));
+            GrayScott::add_spot(&mut data, size, center, radius, rng);
+        }
+        data
+    }
+
+    fn add_spot(
+        data: &mut [ChemicalData],
+        size: (u16, u16),
+        center: (u16, u16),
+        radius: u16,
+        rng: &mut impl Rng,
+    ) {
+        let (w, h) = (size.0 as i32, size.1 as i32);
+        let r = radius as i32;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let x = (center.0 as i32 + dx).rem_euclid(w);
+                let y = (center.1 as i32 + dy).rem_euclid(h);
+                data[(y * w + x) as usize] = ChemicalData::new(0.5, 0.25 + rng.gen::<f32>() * 0.05);
+            }
+        }
+    }
+}
+
+impl RuleSet for GrayScott {
+    type Data = ChemicalData;
+    const SOURCE_SIZE: u8 = 3;
+
+    /// A single integration step, used when cells are updated one by one.
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let (lu, lv) = source
+            .iter()
+            .zip(LAPLACIAN.iter())
+            .fold((0.0, 0.0), |acc, (d, w)| (acc.0 + d.u * w, acc.1 + d.v * w));
+        self.react(source[4], lu, lv)
+    }
+
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        let size = grid.get_size();
+        let (w, h) = (size.width as usize, size.height as usize);
+        let mut current = grid.get_raw_data().to_vec();
+        let mut next = current.clone();
+        for _ in 0..self.steps {
+            for y in 0..h {
+                let rows = [(y + h - 1) % h, y, (y + 1) % h];
+                for x in 0..w {
+                    let columns = [(x + w - 1) % w, x, (x + 1) % w];
+                    let (mut lu, mut lv) = (0.0, 0.0);
+                    for (i, row) in rows.iter().enumerate() {
+                        for (j, column) in columns.iter().enumerate() {
+                            let d = &current[row * w + column];
+                            let weight = LAPLACIAN[i * 3 + j];
+                            lu += d.u * weight;
+                            lv += d.v * weight;
+                        }
+                    }
+                    next[y * w + x] = self.react(&current[y * w + x], lu, lv);
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        Some(current)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![ChemicalData::new(1.0, 0.0), ChemicalData::new(0.5, 0.25)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn values(data: &[ChemicalData]) -> Vec<(f32, f32)> {
+        data.iter().map(|c| (c.u(), c.v())).collect()
+    }
+
+    #[test]
+    fn rejects_unstable_parameters() {
+        assert!(GrayScott::new(0.2, 0.06).is_err());
+        assert!(GrayScott::coral().with_diffusion(1.2, 0.5).is_ok());
+        assert!(GrayScott::coral().with_diffusion(1.25, 0.5).is_err());
+        assert!(GrayScott::coral().with_diffusion(0.5, 1.3).is_err());
+        assert!(GrayScott::coral().with_time_step(1.2).is_ok());
+        assert!(GrayScott::coral().with_time_step(1.25).is_err());
+        assert!(GrayScott::coral().with_time_step(0.0).is_err());
+        let slow = GrayScott::coral().with_time_step(0.5).unwrap();
+        assert!(slow.with_diffusion(2.0, 1.0).is_ok());
+    }
+
+    #[test]
+    fn uniform_field_stays_fixed() {
+        let rule = GrayScott::mitosis();
+        let data = vec![ChemicalData::new(1.0, 0.0); 64];
+        let grid = Grid::init_with_data(data.clone(), 8).unwrap();
+        let next = rule.next_generation(&grid, 0).unwrap();
+        assert_eq!(values(&next), values(&data));
+        let window: Vec<&ChemicalData> = data[..9].iter().collect();
+        assert_eq!(values(&[rule.next(&window)]), [(1.0, 0.0)]);
+    }
+
+    #[test]
+    fn seeded_square_repeats() {
+        let seeded = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            values(&GrayScott::seed_square((16, 12), (8, 6), 2, &mut rng))
+        };
+        let square = seeded(4);
+        assert_eq!(square, seeded(4));
+        assert_ne!(square, seeded(5));
+        for (i, &(u, v)) in square.iter().enumerate() {
+            let (x, y) = (i % 16, i / 16);
+            if (6..=10).contains(&x) && (4..=8).contains(&y) {
+                assert!(u == 0.5 && (0.25..=0.3).contains(&v));
+            } else {
+                assert_eq!((u, v), (1.0, 0.0));
+            }
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use std::ops::{Index, IndexMut};
+use std::sync::Arc;
 
-use crate::{DataType, GError, GResult, IndexType, PrintableDataType, Size};
+use crate::{
+    DataType, GError, GResult, IndexType, PrintableDataType, Size, Square, TextLayout, Topology,
+};
 
 #[derive(Clone)]
 pub struct Grid<D> {
     width: u16,
     height: u16,
+    topology: Arc<dyn Topology>,
     data: Box<[D]>,
 }
 
@@ -19,11 +23,22 @@ impl<D: DataType> Grid<D> {
             Ok(Grid {
                 width,
                 height: (size / uw) as u16,
+                topology: Arc::new(Square),
                 data: init_data.into_boxed_slice(),
             })
         }
     }
 
+    pub fn with_topology<T: Topology>(mut self, topology: T) -> GResult<Grid<D>> {
+        topology.check_size(self.width, self.height)?;
+        self.topology = Arc::new(topology);
+        Ok(self)
+    }
+
+    pub fn get_topology(&self) -> &dyn Topology {
+        self.topology.as_ref()
+    }
+
     pub fn get_size(&self) -> Size {
         Size {
             width: self.width,
@@ -39,17 +54,15 @@ impl<D: DataType> Grid<D> {
     }
 
     pub fn get_area(&self, index: IndexType, size: u8) -> Vec<&D> {
-        let mut v = Vec::new();
-        let x = index.0 as i32;
-        let y = index.1 as i32;
-        let half_size = (size / 2) as i32;
-        for h in y - half_size..=y + half_size {
-            for w in x - half_size..=x + half_size {
-                let (x, y) = self.wrap((w, h));
-                v.push(&self.data[y * (self.width as usize) + x]);
-            }
-        }
-        v
+        let mut cells = Vec::with_capacity(size as usize * size as usize);
+        self.topology.window(index, size, &mut cells);
+        cells
+            .into_iter()
+            .map(|c| {
+                let (x, y) = self.wrap(c);
+                &self.data[y * (self.width as usize) + x]
+            })
+            .collect()
     }
 
     pub fn get_raw_data(&self) -> &[D] {
@@ -62,11 +75,19 @@ impl<D: DataType> Grid<D> {
 
 impl<D: PrintableDataType> Grid<D> {
     pub fn print(&self) {
-        for (i, v) in self.data.iter().enumerate() {
-            print!("{}", v.get_char());
-            if (i + 1) % self.width as usize == 0 {
-                print!("\n");
+        let hexagonal = self.topology.text_layout() == TextLayout::Staggered;
+        for (y, row) in self.data.chunks(self.width as usize).enumerate() {
+            if hexagonal && y % 2 == 0 {
+                print!(" ");
+            }
+            for v in row {
+                if hexagonal {
+                    print!("{} ", v.get_char());
+                } else {
+                    print!("{}", v.get_char());
+                }
             }
+            println!();
         }
     }
 }
@@ -1,4 +1,7 @@
-use game_of_life::{DataType, ColoredDataType, RandomInit, RuleSet, Color};
+use game_of_life::{DataType, ColoredDataType, RandomInit, RuleSet, Color, Layered};
+use rand::Rng;
+
+use crate::conway_rules::BoolData;
 
 #[derive(Clone)]
 pub struct HeatData {
@@ -21,21 +24,21 @@ impl ColoredDataType for HeatData {
 }
 
 impl RandomInit for HeatData {
-    fn rnd() -> Self {
-        let value = rand::random::<f32>();
-        let factor = rand::random::<f32>() * 0.3 + 0.05;
+    fn rnd(rng: &mut impl Rng) -> Self {
+        let value = rng.gen::<f32>();
+        let factor = rng.gen::<f32>() * 0.3 + 0.05;
         HeatData { value, factor }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct HeatRules {}
 
 impl RuleSet for HeatRules {
     type Data = HeatData;
     const SOURCE_SIZE: u8 = 3;
 
-    fn next(source: &[&Self::Data]) -> Self::Data {
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
         let me = source[4].value;
         let factor = source[4].factor;
         let avg = (source.iter().fold(0.0, |acc, d| acc + d.value) - me) / 8.0;
@@ -43,6 +46,12 @@ impl RuleSet for HeatRules {
         HeatData { value: new_value, factor }
         //HeatData{value: source[4].value}
     }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        [0.0, 1.0]
+            .map(|value| HeatData { value, factor: 0.2 })
+            .to_vec()
+    }
 }
 
 fn generate_heat_data(size: (u16, u16)) -> Vec<HeatData> {
@@ -51,4 +60,47 @@ fn generate_heat_data(size: (u16, u16)) -> Vec<HeatData> {
     data[l / 2].value = 1.0;
 
     data
-}
\ No newline at end of file
+}
+
+/// Conway cells on a temperature field: live cells warm their spot, warm
+/// spots let crowded cells survive and cold ones kill lonely cells.
+#[derive(Clone, Default)]
+pub struct HeatedConway {
+    heat: HeatRules,
+}
+
+impl RuleSet for HeatedConway {
+    type Data = Layered<BoolData, HeatData>;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let (cells, heat) = Layered::split(source);
+        let me = cells[4].is_alive();
+        let neighbours = cells.iter().filter(|c| c.is_alive()).count() - me as usize;
+        let temperature = heat[4].value;
+        let alive = match (me, neighbours) {
+            (true, 4) => temperature > 0.6,
+            (true, 2) => temperature > 0.2,
+            (_, 3) => true,
+            _ => false,
+        };
+        let mut bottom = self.heat.next(&heat);
+        if alive {
+            bottom.value = (bottom.value + 0.05).min(1.0);
+        }
+        heated(alive, bottom)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        let heat = self.heat.palette();
+        vec![
+            heated(false, heat[0].clone()),
+            heated(true, heat[0].clone()),
+            heated(false, heat[1].clone()),
+        ]
+    }
+}
+
+fn heated(alive: bool, heat: HeatData) -> Layered<BoolData, HeatData> {
+    Layered::new(("life", BoolData::from(alive)), ("heat", heat))
+}
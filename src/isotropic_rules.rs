@@ -0,0 +1,340 @@
+use rand::Rng;
+
+use crate::{
+    Color, ColoredDataType, DataType, GError, GResult, PrintableDataType, RandomInit, RuleSet,
+};
+
+const NEIGHBOURS: u16 = 0b1_1110_1111;
+const CENTRE: u16 = 0b1_0000;
+
+/// Hensel letters per neighbour count together with a representative
+/// configuration of each. Bits follow the raster order of the 3x3 source
+/// window (bit 0 is the north-west cell, bit 4 the centre). Counts above
+/// four use the complements of the representatives for `8 - count`.
+const LETTERS: [(&str, &[u16]); 5] = [
+    ("", &[0]),
+    ("ce", &[1, 2]),
+    ("ceaikn", &[5, 10, 3, 40, 33, 68]),
+    ("ceaiknjqry", &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97]),
+    (
+        "ceaiknjqrtwyz",
+        &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+    ),
+];
+
+#[derive(Clone)]
+pub struct LifeCell {
+    alive: bool,
+}
+
+impl LifeCell {
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+}
+
+impl From<bool> for LifeCell {
+    fn from(alive: bool) -> Self {
+        LifeCell { alive }
+    }
+}
+
+impl DataType for LifeCell {}
+
+impl RandomInit for LifeCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        LifeCell { alive: rng.gen() }
+    }
+}
+
+impl ColoredDataType for LifeCell {
+    fn get_color(&self) -> Color {
+        if self.alive {
+            (255, 255, 255, 255)
+        } else {
+            (0, 0, 0, 255)
+        }
+    }
+}
+
+impl PrintableDataType for LifeCell {
+    fn get_char(&self) -> char {
+        if self.alive {
+            '*'
+        } else {
+            '_'
+        }
+    }
+}
+
+/// Isotropic non-totalistic rule such as `B2-a/S12`, evaluated through a
+/// lookup over every state of the 3x3 window.
+#[derive(Clone)]
+pub struct IsotropicRule {
+    rule: String,
+    table: [bool; 512],
+}
+
+impl Default for IsotropicRule {
+    fn default() -> Self {
+        IsotropicRule::parse("B3/S23").unwrap()
+    }
+}
+
+impl IsotropicRule {
+    /// Accepts `B.../S...` in any case and order, with or without the slash,
+    /// and the older `S/B` digit form such as `23/3`.
+    pub fn parse(rule: &str) -> GResult<IsotropicRule> {
+        let err = |message: String| GError::RuleStringError {
+            rule: rule.to_string(),
+            message,
+        };
+        let lower = rule.trim().to_ascii_lowercase();
+        let (birth, survival) = if lower.starts_with('b') || lower.starts_with('s') {
+            let mut birth = String::new();
+            let mut survival = String::new();
+            let mut target = None;
+            for c in lower.chars().filter(|&c| c != '/') {
+                match c {
+                    'b' => target = Some(&mut birth),
+                    's' => target = Some(&mut survival),
+                    c => target.as_mut().unwrap().push(c),
+                }
+            }
+            (birth, survival)
+        } else {
+            let (survival, birth) = lower
+                .split_once('/')
+                .ok_or_else(|| err("expected B/S or S/B notation".to_string()))?;
+            (birth.to_string(), survival.to_string())
+        };
+
+        let classes = Classes::new();
+        let birth = parse_conditions(&birth).map_err(err)?;
+        let survival = parse_conditions(&survival).map_err(err)?;
+        let mut table = [false; 512];
+        for (index, next) in table.iter_mut().enumerate() {
+            let neighbours = index as u16 & NEIGHBOURS;
+            let (count, letter) = classes.of[neighbours as usize];
+            let conditions = if index as u16 & CENTRE != 0 {
+                &survival
+            } else {
+                &birth
+            };
+            *next = conditions[count as usize] & (1 << letter) != 0;
+        }
+        Ok(IsotropicRule {
+            rule: rule.trim().to_string(),
+            table,
+        })
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+}
+
+impl RuleSet for IsotropicRule {
+    type Data = LifeCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let index = source
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, c)| acc | ((c.alive as usize) << i));
+        LifeCell {
+            alive: self.table[index],
+        }
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![LifeCell::from(false), LifeCell::from(true)]
+    }
+}
+
+/// Maps every neighbour configuration to its count and letter index.
+struct Classes {
+    of: Vec<(u8, u8)>,
+}
+
+impl Classes {
+    fn new() -> Classes {
+        let mut of = vec![(0, 0); 512];
+        for count in 0..=8 {
+            let (_, reps) = LETTERS[count.min(8 - count)];
+            for (letter, &rep) in reps.iter().enumerate() {
+                let rep = if count > 4 { !rep & NEIGHBOURS } else { rep };
+                for config in orbit(rep) {
+                    of[config as usize] = (count as u8, letter as u8);
+                }
+            }
+        }
+        Classes { of }
+    }
+}
+
+/// All rotations and reflections of a 3x3 configuration.
+fn orbit(config: u16) -> Vec<u16> {
+    let transform = |config: u16, f: &dyn Fn(usize, usize) -> (usize, usize)| {
+        (0..9)
+            .filter(|i| config & (1 << i) != 0)
+            .fold(0u16, |acc, i| {
+                let (x, y) = f(i % 3, i / 3);
+                acc | 1 << (y * 3 + x)
+            })
+    };
+    let mut all = Vec::with_capacity(8);
+    let mut current = config;
+    for _ in 0..4 {
+        all.push(current);
+        all.push(transform(current, &|x, y| (2 - x, y)));
+        current = transform(current, &|x, y| (2 - y, x));
+    }
+    all
+}
+
+/// Returns for each neighbour count a bitmask of the accepted letters.
+fn parse_conditions(text: &str) -> Result<[u16; 9], String> {
+    let mut conditions = [0u16; 9];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c
+            .to_digit(10)
+            .filter(|&d| d <= 8)
+            .ok_or_else(|| format!("unexpected '{}'", c))? as usize;
+        let (letters, _) = LETTERS[count.min(8 - count)];
+        let all = if letters.is_empty() {
+            1
+        } else {
+            (1 << letters.len()) - 1
+        };
+        let negate = chars.next_if_eq(&'-').is_some();
+        let mut selected = 0u16;
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            let index = letters
+                .find(letter)
+                .ok_or_else(|| format!("'{}{}' is not a valid neighbourhood", count, letter))?;
+            selected |= 1 << index;
+        }
+        conditions[count] |= match (negate, selected) {
+            (false, 0) => all,
+            (true, 0) => return Err(format!("'{}-' needs letters", count)),
+            (false, selected) => selected,
+            (true, selected) => all & !selected,
+        };
+    }
+    Ok(conditions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(rule: &IsotropicRule, bits: u16) -> bool {
+        let window: Vec<LifeCell> = (0..9).map(|i| LifeCell::from(bits >> i & 1 == 1)).collect();
+        rule.next(&window.iter().collect::<Vec<_>>()).is_alive()
+    }
+
+    #[test]
+    fn totalistic_rule_matches_life() {
+        for text in ["B3/S23", "b3s23", "S23/B3", "23/3"] {
+            let rule = IsotropicRule::parse(text).unwrap();
+            for bits in 0..512u16 {
+                let count = (bits & NEIGHBOURS).count_ones();
+                let alive = bits & CENTRE != 0;
+                let expected = count == 3 || alive && count == 2;
+                assert_eq!(next(&rule, bits), expected, "{} on {:09b}", text, bits);
+            }
+        }
+    }
+
+    #[test]
+    fn negated_letters_are_left_out() {
+        let rule = IsotropicRule::parse("B2-a/S12").unwrap();
+        // 2a: north-west and north; 2c: north-west and north-east.
+        assert!(!next(&rule, 0b000_000_011));
+        assert!(next(&rule, 0b000_000_101));
+        assert!(!next(&rule, 0b000_000_001));
+        assert!(next(&rule, 0b000_010_001));
+        assert!(next(&rule, 0b000_010_011));
+        assert!(!next(&rule, 0b000_010_111));
+    }
+
+    /// A 3x3 window drawn as rows of `O` and `.`.
+    fn picture(rows: [&str; 3]) -> u16 {
+        rows.concat()
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| c == 'O')
+            .fold(0, |acc, (i, _)| acc | 1 << i)
+    }
+
+    #[test]
+    fn letters_match_their_pictures() {
+        let knight = IsotropicRule::parse("B3k/S").unwrap();
+        let y = IsotropicRule::parse("B3y/S").unwrap();
+        let k_pictures = [
+            [".O.", "..O", "O.."],
+            ["O..", "..O", ".O."],
+            ["..O", "O..", ".O."],
+        ];
+        let y_pictures = [
+            ["O..", "..O", "O.."],
+            ["O.O", "...", ".O."],
+            [".O.", "...", "O.O"],
+        ];
+        for rows in k_pictures {
+            assert!(next(&knight, picture(rows)), "3k {:?}", rows);
+            assert!(!next(&y, picture(rows)), "3y {:?}", rows);
+        }
+        for rows in y_pictures {
+            assert!(next(&y, picture(rows)), "3y {:?}", rows);
+            assert!(!next(&knight, picture(rows)), "3k {:?}", rows);
+        }
+        let others = IsotropicRule::parse("B3ceai/S").unwrap();
+        for rows in [
+            ["O.O", "...", "O.."],
+            [".O.", "O.O", "..."],
+            ["OO.", "O..", "..."],
+            ["...", "...", "OOO"],
+        ] {
+            assert!(next(&others, picture(rows)), "{:?}", rows);
+            assert!(!next(&knight, picture(rows)), "{:?}", rows);
+        }
+        let births = |rule: &IsotropicRule| (0..512).filter(|&b| next(rule, b)).count();
+        assert_eq!(births(&knight), 4);
+        assert_eq!(births(&y), 4);
+    }
+
+    #[test]
+    fn hensel_orbits_partition_every_count() {
+        let classes = Classes::new();
+        let mut sizes = [[0usize; 13]; 9];
+        for config in (0..512u16).filter(|c| c & !NEIGHBOURS == 0) {
+            let (count, letter) = classes.of[config as usize];
+            assert_eq!(count as u32, config.count_ones());
+            sizes[count as usize][letter as usize] += 1;
+        }
+        let binomial = [1, 8, 28, 56, 70, 56, 28, 8, 1];
+        for count in 0..=8 {
+            let (letters, _) = LETTERS[count.min(8 - count)];
+            let used = sizes[count].iter().filter(|&&n| n > 0).count();
+            assert_eq!(used, letters.len().max(1), "count {}", count);
+            assert_eq!(sizes[count].iter().sum::<usize>(), binomial[count]);
+        }
+        // c, e, a, i, k and n.
+        assert_eq!(sizes[2][..6], [4, 4, 8, 2, 8, 2]);
+        // c, e, a, i, k, n, j, q, r and y.
+        assert_eq!(sizes[3][..10], [4, 4, 4, 4, 4, 8, 8, 8, 8, 4]);
+        assert_eq!(orbit(1).len(), 8);
+    }
+
+    #[test]
+    fn bad_letters_are_rejected() {
+        assert!(IsotropicRule::parse("B2x/S23").is_err());
+        assert!(IsotropicRule::parse("B1a/S23").is_err());
+        assert!(IsotropicRule::parse("B3-/S23").is_err());
+        assert!(IsotropicRule::parse("B9/S23").is_err());
+    }
+}
@@ -0,0 +1,465 @@
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, GError, GResult, Grid, RandomInit, RuleSet};
+
+const MAX_RANGE: u16 = 127;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Shape {
+    Moore,
+    VonNeumann,
+    Circular,
+}
+
+#[derive(Clone)]
+pub struct LtlCell {
+    state: u8,
+    color: Color,
+}
+
+impl LtlCell {
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+}
+
+impl From<bool> for LtlCell {
+    fn from(alive: bool) -> Self {
+        if alive {
+            LtlCell {
+                state: 1,
+                color: (255, 255, 255, 255),
+            }
+        } else {
+            LtlCell {
+                state: 0,
+                color: (0, 0, 0, 255),
+            }
+        }
+    }
+}
+
+impl DataType for LtlCell {}
+
+impl RandomInit for LtlCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        LtlCell::from(rng.gen::<bool>())
+    }
+}
+
+impl ColoredDataType for LtlCell {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+/// Larger than Life rule in Golly's `R5,C0,M1,S34..58,B34..45,NM` notation.
+/// With more than two states (`C`), live cells that fail to survive decay
+/// through the extra states before dying, as in Generations rules.
+///
+/// Generations of the Moore (`NM`) and von Neumann (`NN`) shapes take time
+/// independent of the range. The circular shape (`NC`) does not: it adds up
+/// one row segment per row of the neighbourhood, so its cost per cell grows
+/// with the range, though far slower than visiting every neighbour.
+#[derive(Clone)]
+pub struct LargerThanLife {
+    range: u16,
+    states: u8,
+    middle: bool,
+    survival: RangeInclusive<u32>,
+    birth: RangeInclusive<u32>,
+    shape: Shape,
+}
+
+impl Default for LargerThanLife {
+    fn default() -> Self {
+        LargerThanLife::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap()
+    }
+}
+
+impl LargerThanLife {
+    pub fn parse(rule: &str) -> GResult<LargerThanLife> {
+        let err = |message: String| GError::RuleStringError {
+            rule: rule.to_string(),
+            message,
+        };
+        let mut range = None;
+        let mut states = 2;
+        let mut middle = false;
+        let mut survival = None;
+        let mut birth = None;
+        let mut shape = Shape::Moore;
+        for part in rule.trim().split(',').map(str::trim) {
+            let (key, value) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+            match key.to_ascii_uppercase().as_str() {
+                "R" => {
+                    let r = parse_number(value).map_err(err)?;
+                    if !(1..=MAX_RANGE as u32).contains(&r) {
+                        return Err(err(format!("range must be in 1..={}", MAX_RANGE)));
+                    }
+                    range = Some(r as u16);
+                }
+                "C" => {
+                    let c = parse_number(value).map_err(err)?;
+                    if c > 255 {
+                        return Err(err("at most 255 states are supported".to_string()));
+                    }
+                    states = (c as u8).max(2);
+                }
+                "M" => middle = parse_number(value).map_err(err)? != 0,
+                "S" => survival = Some(parse_range(value).map_err(err)?),
+                "B" => birth = Some(parse_range(value).map_err(err)?),
+                "N" => {
+                    shape = match value.to_ascii_uppercase().as_str() {
+                        "M" => Shape::Moore,
+                        "N" => Shape::VonNeumann,
+                        "C" => Shape::Circular,
+                        n => return Err(err(format!("unknown neighbourhood 'N{}'", n))),
+                    }
+                }
+                _ => return Err(err(format!("unexpected '{}'", part))),
+            }
+        }
+        Ok(LargerThanLife {
+            range: range.ok_or_else(|| err("R is missing".to_string()))?,
+            states,
+            middle,
+            survival: survival.ok_or_else(|| err("S is missing".to_string()))?,
+            birth: birth.ok_or_else(|| err("B is missing".to_string()))?,
+            shape,
+        })
+    }
+
+    pub fn range(&self) -> u16 {
+        self.range
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn cell(&self, state: u8) -> LtlCell {
+        let color = match state {
+            0 => (0, 0, 0, 255),
+            1 => (255, 255, 255, 255),
+            s => {
+                let fade = 1.0 - (s - 1) as f32 / (self.states - 1) as f32;
+                let v = (fade * 200.0) as u8;
+                (v, v / 3, 0, 255)
+            }
+        };
+        LtlCell { state, color }
+    }
+
+    fn half_width(&self, dy: i32) -> Option<i32> {
+        let r = self.range as i32;
+        let half = match self.shape {
+            Shape::Moore => r,
+            Shape::VonNeumann => r - dy.abs(),
+            Shape::Circular => (((r * r + r - dy * dy) as f64).sqrt()) as i32,
+        };
+        (half >= 0 && dy.abs() <= r).then_some(half)
+    }
+
+    fn apply(&self, me: u8, live_neighbours: u32) -> LtlCell {
+        let next = match me {
+            0 if self.birth.contains(&live_neighbours) => 1,
+            0 => 0,
+            1 if self.survival.contains(&live_neighbours) => 1,
+            s => (s + 1) % self.states,
+        };
+        self.cell(next)
+    }
+}
+
+impl RuleSet for LargerThanLife {
+    type Data = LtlCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn source_size(&self) -> u8 {
+        (self.range * 2 + 1) as u8
+    }
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let size = self.source_size() as i32;
+        let r = self.range as i32;
+        let mut count = 0;
+        for dy in -r..=r {
+            let half = match self.half_width(dy) {
+                Some(half) => half,
+                None => continue,
+            };
+            let row = (dy + r) * size;
+            for dx in -half..=half {
+                count += (source[(row + dx + r) as usize].state == 1) as u32;
+            }
+        }
+        let me = source[source.len() / 2].state;
+        if !self.middle && me == 1 {
+            count -= 1;
+        }
+        self.apply(me, count)
+    }
+
+    /// Counts live cells with a summed-area table for the Moore shape and a
+    /// turned one for the von Neumann shape, so a generation costs the same
+    /// at any range. The circular shape sums one prefix-summed row segment
+    /// per row of the neighbourhood.
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        let size = grid.get_size();
+        let (w, h) = (size.width as usize, size.height as usize);
+        let data = grid.get_raw_data();
+        let table = SummedArea::new(data, w, h);
+        let diamonds =
+            (self.shape == Shape::VonNeumann).then(|| DiamondArea::new(data, w, h, self.range));
+        let r = self.range as i64;
+        let mut next = Vec::with_capacity(data.len());
+        for y in 0..h as i64 {
+            for x in 0..w as i64 {
+                let mut count = match self.shape {
+                    Shape::Moore => table.sum(x - r, 2 * r + 1, y - r, 2 * r + 1),
+                    Shape::VonNeumann => diamonds.as_ref().unwrap().sum(x as usize, y as usize),
+                    Shape::Circular => (-r..=r)
+                        .filter_map(|dy| self.half_width(dy as i32).map(|half| (dy, half as i64)))
+                        .map(|(dy, half)| table.sum(x - half, 2 * half + 1, y + dy, 1))
+                        .sum(),
+                };
+                let me = data[y as usize * w + x as usize].state;
+                if !self.middle && me == 1 {
+                    count -= 1;
+                }
+                next.push(self.apply(me, count as u32));
+            }
+        }
+        Some(next)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        (0..self.states).map(|s| self.cell(s)).collect()
+    }
+}
+
+/// Live-cell counts of any rectangle on the torus in constant time.
+struct SummedArea {
+    width: usize,
+    height: usize,
+    // (width + 1) * (height + 1) entries with a zero first row and column
+    sums: Vec<i64>,
+}
+
+impl SummedArea {
+    fn new(data: &[LtlCell], width: usize, height: usize) -> SummedArea {
+        let stride = width + 1;
+        let mut sums = vec![0; stride * (height + 1)];
+        for y in 0..height {
+            let mut row = 0;
+            for x in 0..width {
+                row += (data[y * width + x].state == 1) as i64;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+            }
+        }
+        SummedArea {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    fn rect(&self, x: usize, len_x: usize, y: usize, len_y: usize) -> i64 {
+        let stride = self.width + 1;
+        let at = |x: usize, y: usize| self.sums[y * stride + x];
+        at(x + len_x, y + len_y) - at(x, y + len_y) - at(x + len_x, y) + at(x, y)
+    }
+
+    /// Sums `len_x` by `len_y` cells starting at (`x`, `y`), wrapping around
+    /// the edges as often as needed.
+    fn sum(&self, x: i64, len_x: i64, y: i64, len_y: i64) -> i64 {
+        let xs = wrap_segments(x, len_x, self.width);
+        let ys = wrap_segments(y, len_y, self.height);
+        let mut total = 0;
+        for &(x, len_x, times_x) in &xs {
+            for &(y, len_y, times_y) in &ys {
+                total += times_x * times_y * self.rect(x, len_x, y, len_y);
+            }
+        }
+        total
+    }
+}
+
+/// Live-cell counts of von Neumann diamonds of one range on the torus in
+/// constant time. The grid is padded with `range` wrapped cells on every
+/// side and turned by 45 degrees, `u = x + y` and `v = x - y`, which makes
+/// every diamond a square of a summed-area table.
+struct DiamondArea {
+    range: usize,
+    padded_height: usize,
+    // (size + 1) * (size + 1) entries with a zero first row and column
+    size: usize,
+    sums: Vec<i32>,
+}
+
+impl DiamondArea {
+    fn new(data: &[LtlCell], width: usize, height: usize, range: u16) -> DiamondArea {
+        let range = range as usize;
+        let (padded_width, padded_height) = (width + 2 * range, height + 2 * range);
+        let size = padded_width + padded_height - 1;
+        let stride = size + 1;
+        let mut sums = vec![0; stride * stride];
+        for py in 0..padded_height {
+            let y = (py + height * range - range) % height;
+            for px in 0..padded_width {
+                let x = (px + width * range - range) % width;
+                if data[y * width + x].state == 1 {
+                    let (u, v) = (px + py, px + padded_height - 1 - py);
+                    sums[(v + 1) * stride + u + 1] = 1;
+                }
+            }
+        }
+        for v in 1..=size {
+            let mut row = 0;
+            for u in 1..=size {
+                row += sums[v * stride + u];
+                sums[v * stride + u] = sums[(v - 1) * stride + u] + row;
+            }
+        }
+        DiamondArea {
+            range,
+            padded_height,
+            size,
+            sums,
+        }
+    }
+
+    /// Live cells within `range` steps of (`x`, `y`).
+    fn sum(&self, x: usize, y: usize) -> i64 {
+        let stride = self.size + 1;
+        let (px, py) = (x + self.range, y + self.range);
+        let (u, v) = (px + py, px + self.padded_height - 1 - py);
+        let (u0, u1) = (u - self.range, u + self.range + 1);
+        let (v0, v1) = (v - self.range, v + self.range + 1);
+        let at = |u: usize, v: usize| self.sums[v * stride + u] as i64;
+        at(u1, v1) - at(u0, v1) - at(u1, v0) + at(u0, v0)
+    }
+}
+
+/// Splits a cyclic range into non-wrapping `(start, length, multiplicity)`
+/// pieces.
+fn wrap_segments(start: i64, len: i64, n: usize) -> Vec<(usize, usize, i64)> {
+    let n_i = n as i64;
+    let mut segments = Vec::with_capacity(3);
+    if len >= n_i {
+        segments.push((0, n, len / n_i));
+    }
+    let rest = (len % n_i) as usize;
+    let start = start.rem_euclid(n_i) as usize;
+    if start + rest <= n {
+        segments.push((start, rest, 1));
+    } else {
+        segments.push((start, n - start, 1));
+        segments.push((0, start + rest - n, 1));
+    }
+    segments
+}
+
+fn parse_number(value: &str) -> Result<u32, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))
+}
+
+fn parse_range(value: &str) -> Result<RangeInclusive<u32>, String> {
+    match value.split_once("..") {
+        Some((low, high)) => Ok(parse_number(low)?..=parse_number(high)?),
+        None => {
+            let n = parse_number(value)?;
+            Ok(n..=n)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Runs `rule` on every cell of a torus through `next`, one window at a
+    /// time.
+    fn next_per_cell(rule: &LargerThanLife, data: &[LtlCell], w: i32, h: i32) -> Vec<u8> {
+        let r = rule.range() as i32;
+        let mut next = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let window: Vec<&LtlCell> = (-r..=r)
+                    .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+                    .map(|(dx, dy)| {
+                        let i = (y + dy).rem_euclid(h) * w + (x + dx).rem_euclid(w);
+                        &data[i as usize]
+                    })
+                    .collect();
+                next.push(rule.next(&window).state());
+            }
+        }
+        next
+    }
+
+    #[test]
+    fn range_one_matches_life() {
+        let rule = LargerThanLife::parse("R1,C0,M0,S2..3,B3..3,NM").unwrap();
+        for bits in 0..512u16 {
+            let window: Vec<LtlCell> = (0..9).map(|i| LtlCell::from(bits >> i & 1 == 1)).collect();
+            let count = (bits & !0b1_0000).count_ones();
+            let alive = bits & 0b1_0000 != 0;
+            let expected = count == 3 || alive && count == 2;
+            let next = rule.next(&window.iter().collect::<Vec<_>>());
+            assert_eq!(next.state(), expected as u8, "window {:09b}", bits);
+        }
+    }
+
+    #[test]
+    fn whole_grid_matches_per_cell_for_every_shape() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for ((w, h), text) in [
+            ((17, 13), "R3,C0,M1,S10..20,B12..18,NM"),
+            ((17, 13), "R3,C0,M0,S6..10,B7..9,NN"),
+            ((17, 13), "R3,C0,M1,S14..24,B15..20,NC"),
+            ((5, 7), "R1,C0,M1,S2..3,B2..3,NN"),
+            ((7, 5), "R9,C0,M0,S60..90,B70..80,NN"),
+            ((6, 4), "R8,C0,M1,S60..120,B70..100,NM"),
+        ] {
+            let data: Vec<LtlCell> = (0..w * h).map(|_| LtlCell::rnd(&mut rng)).collect();
+            let grid = Grid::init_with_data(data.clone(), w as u16).unwrap();
+            let rule = LargerThanLife::parse(text).unwrap();
+            let whole: Vec<u8> = rule
+                .next_generation(&grid, 0)
+                .unwrap()
+                .iter()
+                .map(LtlCell::state)
+                .collect();
+            assert_eq!(whole, next_per_cell(&rule, &data, w, h), "{}", text);
+        }
+    }
+
+    #[test]
+    fn cells_decay_through_extra_states() {
+        let rule = LargerThanLife::parse("R1,C4,M0,S9..9,B3..3,NM").unwrap();
+        assert_eq!(rule.states(), 4);
+        let cells: Vec<u8> = (0..4).map(|s| rule.apply(s, 0).state()).collect();
+        assert_eq!(cells, [0, 2, 3, 0]);
+    }
+
+    #[test]
+    fn bad_rules_are_rejected() {
+        assert!(LargerThanLife::parse("C0,M0,S2..3,B3..3,NM").is_err());
+        assert!(LargerThanLife::parse("R0,C0,M0,S2..3,B3..3,NM").is_err());
+        assert!(LargerThanLife::parse("R1,C0,M0,S2..3,B3..3,NX").is_err());
+        assert!(LargerThanLife::parse("R1,C0,M0,S2..3,NM").is_err());
+        assert!(LargerThanLife::parse("R1,C0,M0,S2..3,B3..3,Ré").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, PrintableDataType, RandomInit};
+
+/// Two fields sharing a grid, such as live cells on top of a temperature
+/// field. Rules over `Layered` cells see the neighbourhood of every layer in
+/// the same window, so one layer can drive the other. Nest them, as in
+/// `Layered<A, Layered<B, C>>`, for more than two layers.
+///
+/// Layers are numbered from the top: those of `top` first, then those of
+/// `bottom`.
+#[derive(Clone, Default, Debug)]
+pub struct Layered<A, B> {
+    pub top: A,
+    pub bottom: B,
+    names: (&'static str, &'static str),
+}
+
+impl<A, B> Layered<A, B> {
+    /// Stacks `top` on `bottom`, each with the name `layer_names` gives it,
+    /// as in `Layered::new(("life", cell), ("heat", temperature))`.
+    pub fn new(top: (&'static str, A), bottom: (&'static str, B)) -> Layered<A, B> {
+        Layered {
+            top: top.1,
+            bottom: bottom.1,
+            names: (top.0, bottom.0),
+        }
+    }
+
+    /// Splits a window of layered cells into the windows of each layer, so
+    /// rules written for a single layer can be reused.
+    pub fn split<'a>(source: &[&'a Layered<A, B>]) -> (Vec<&'a A>, Vec<&'a B>) {
+        source.iter().map(|c| (&c.top, &c.bottom)).unzip()
+    }
+}
+
+impl<A: DataType, B: DataType> DataType for Layered<A, B> {}
+
+impl<A: ColoredDataType, B: ColoredDataType> ColoredDataType for Layered<A, B> {
+    /// The average of the layer colours.
+    fn get_color(&self) -> Color {
+        let (na, nb) = (A::layer_count() as u32, B::layer_count() as u32);
+        let (a, b) = (self.top.get_color(), self.bottom.get_color());
+        let mix = |a: u8, b: u8| ((a as u32 * na + b as u32 * nb) / (na + nb)) as u8;
+        (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2), mix(a.3, b.3))
+    }
+
+    fn layer_count() -> usize {
+        A::layer_count() + B::layer_count()
+    }
+
+    /// A layer that is itself `Layered`, or that was left unnamed, keeps the
+    /// names of its own layers.
+    fn layer_names(&self) -> Vec<&'static str> {
+        let named = |name: &'static str, inner: Vec<&'static str>| {
+            if inner.len() == 1 && !name.is_empty() {
+                vec![name]
+            } else {
+                inner
+            }
+        };
+        let mut names = named(self.names.0, self.top.layer_names());
+        names.extend(named(self.names.1, self.bottom.layer_names()));
+        names
+    }
+
+    fn get_layer_color(&self, layer: usize) -> Color {
+        match layer.checked_sub(A::layer_count()) {
+            None => self.top.get_layer_color(layer),
+            Some(layer) => self.bottom.get_layer_color(layer),
+        }
+    }
+}
+
+impl<A: PrintableDataType, B: DataType> PrintableDataType for Layered<A, B> {
+    fn get_char(&self) -> char {
+        self.top.get_char()
+    }
+}
+
+impl<A: RandomInit, B: RandomInit> RandomInit for Layered<A, B> {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        Layered {
+            top: A::rnd(rng),
+            bottom: B::rnd(rng),
+            names: ("", ""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ForestCell, Game, LifeCell, RuleSet};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    type Forest = Layered<LifeCell, ForestCell>;
+
+    fn forest(alive: bool, tree: ForestCell) -> Forest {
+        Layered::new(("life", LifeCell::from(alive)), ("forest", tree))
+    }
+
+    #[derive(Clone)]
+    struct Still;
+
+    impl RuleSet for Still {
+        type Data = Forest;
+        const SOURCE_SIZE: u8 = 1;
+
+        fn next(&self, source: &[&Self::Data]) -> Self::Data {
+            source[0].clone()
+        }
+
+        fn palette(&self) -> Vec<Self::Data> {
+            vec![forest(false, ForestCell::Empty)]
+        }
+    }
+
+    #[test]
+    fn layers_keep_their_names() {
+        assert_eq!(
+            forest(true, ForestCell::Tree).layer_names(),
+            ["life", "forest"]
+        );
+        let nested = Layered::new(
+            ("life", LifeCell::from(true)),
+            ("", forest(false, ForestCell::Tree)),
+        );
+        assert_eq!(nested.layer_names(), ["life", "life", "forest"]);
+        let unnamed = Forest::rnd(&mut StdRng::seed_from_u64(1));
+        assert_eq!(unnamed.layer_names(), ["LifeCell", "ForestCell"]);
+        let game = Game::init_with_rules(Still, vec![unnamed; 4], 2).unwrap();
+        assert_eq!(game.get_layer_names(), ["life", "forest"]);
+    }
+
+    #[test]
+    fn picked_layer_sets_the_colour() {
+        let cell = forest(true, ForestCell::Burning);
+        let mut game = Game::init_with_rules(Still, vec![cell.clone(); 4], 2).unwrap();
+        assert_eq!(game.get_color((0, 0)), cell.get_color());
+        game.next_layer();
+        assert_eq!(game.get_layer(), Some(0));
+        assert_eq!(game.get_color((0, 0)), LifeCell::from(true).get_color());
+        game.next_layer();
+        assert_eq!(game.get_color((0, 0)), ForestCell::Burning.get_color());
+        game.set_layer(Some(2));
+        assert_eq!(game.get_layer(), Some(1));
+        game.next_layer();
+        assert_eq!(game.get_layer(), None);
+        assert_eq!(game.get_color((1, 1)), cell.get_color());
+    }
+}
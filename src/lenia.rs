@@ -0,0 +1,448 @@
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use crate::{Color, ColoredDataType, DataType, GError, GResult, Grid, RandomInit, RuleSet};
+
+const MAX_RADIUS: u16 = 127;
+/// Kernels at least this wide are convolved through the FFT, smaller ones
+/// go through the per-cell window.
+const FFT_RADIUS: u16 = 6;
+
+const COLOR_STOPS: [(f32, f32, f32); 5] = [
+    (0.0, 0.0, 0.0),
+    (30.0, 30.0, 150.0),
+    (0.0, 180.0, 220.0),
+    (250.0, 220.0, 40.0),
+    (220.0, 40.0, 30.0),
+];
+
+#[derive(Clone)]
+pub struct LeniaCell {
+    value: f32,
+}
+
+impl LeniaCell {
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+}
+
+impl From<f32> for LeniaCell {
+    fn from(value: f32) -> Self {
+        LeniaCell {
+            value: value.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl DataType for LeniaCell {}
+
+impl RandomInit for LeniaCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        LeniaCell {
+            value: rng.gen::<f32>(),
+        }
+    }
+}
+
+impl ColoredDataType for LeniaCell {
+    fn get_color(&self) -> Color {
+        let scaled = self.value * (COLOR_STOPS.len() - 1) as f32;
+        let i = (scaled as usize).min(COLOR_STOPS.len() - 2);
+        let t = scaled - i as f32;
+        let (a, b) = (COLOR_STOPS[i], COLOR_STOPS[i + 1]);
+        let mix = |a: f32, b: f32| (a + (b - a) * t) as u8;
+        (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2), 255)
+    }
+}
+
+/// Continuous automaton in the style of Lenia: the neighbourhood is a
+/// weighted sum over concentric rings of `radius` cells, and a Gaussian
+/// growth function centred on `mu` moves every cell by `time_step`.
+#[derive(Clone)]
+pub struct Lenia {
+    radius: u16,
+    mu: f32,
+    sigma: f32,
+    time_step: f32,
+    // (2 * radius + 1)^2 weights in the layout of the source window
+    kernel: Arc<[f32]>,
+    spectrum: Arc<Mutex<Option<Spectrum>>>,
+}
+
+impl Default for Lenia {
+    fn default() -> Self {
+        ORBIUM.rule()
+    }
+}
+
+impl Lenia {
+    /// `peaks` are the heights of the kernel rings from the inside out.
+    pub fn new(radius: u16, peaks: &[f32], mu: f32, sigma: f32, time_step: f32) -> GResult<Lenia> {
+        let err = |message: &str| Err(GError::RuleParameterError(message.to_string()));
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return err("Lenia radius must be between 1 and 127");
+        }
+        if peaks.is_empty() || peaks.iter().all(|&p| p <= 0.0) {
+            return err("Lenia kernel needs at least one positive peak");
+        }
+        if sigma <= 0.0 || time_step <= 0.0 || time_step > 1.0 {
+            return err("Lenia sigma must be positive and the time step in (0, 1]");
+        }
+        Ok(Lenia {
+            radius,
+            mu,
+            sigma,
+            time_step,
+            kernel: ring_kernel(radius, peaks).into(),
+            spectrum: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn radius(&self) -> u16 {
+        self.radius
+    }
+
+    fn growth(&self, potential: f32) -> f32 {
+        let d = (potential - self.mu) / self.sigma;
+        2.0 * (-d * d / 2.0).exp() - 1.0
+    }
+
+    fn update(&self, value: f32, potential: f32) -> LeniaCell {
+        LeniaCell::from(value + self.time_step * self.growth(potential))
+    }
+
+    fn kernel_spectrum(&self, width: usize, height: usize) -> Spectrum {
+        let mut spectrum = self.spectrum.lock().unwrap();
+        match spectrum.as_ref() {
+            Some(s) if s.width == width && s.height == height => s.clone(),
+            _ => {
+                let s = Spectrum::new(&self.kernel, self.radius as usize, width, height);
+                *spectrum = Some(s.clone());
+                s
+            }
+        }
+    }
+}
+
+impl RuleSet for Lenia {
+    type Data = LeniaCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn source_size(&self) -> u8 {
+        (self.radius * 2 + 1) as u8
+    }
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let potential = source
+            .iter()
+            .zip(self.kernel.iter())
+            .map(|(c, k)| c.value * k)
+            .sum();
+        self.update(source[source.len() / 2].value, potential)
+    }
+
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        if self.radius < FFT_RADIUS {
+            return None;
+        }
+        let size = grid.get_size();
+        let (width, height) = (size.width as usize, size.height as usize);
+        let spectrum = self.kernel_spectrum(width, height);
+        let data = grid.get_raw_data();
+        let mut buffer: Vec<Complex<f32>> =
+            data.iter().map(|c| Complex::new(c.value, 0.0)).collect();
+        spectrum.convolve(&mut buffer);
+        Some(
+            data.iter()
+                .zip(buffer.iter())
+                .map(|(c, potential)| self.update(c.value, potential.re))
+                .collect(),
+        )
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![LeniaCell::from(0.0), LeniaCell::from(1.0)]
+    }
+}
+
+/// Rings of the exponential bump `exp(4 - 1 / (r (1 - r)))`, normalised so
+/// the weights sum to one.
+fn ring_kernel(radius: u16, peaks: &[f32]) -> Vec<f32> {
+    let r = radius as i32;
+    let mut kernel = Vec::with_capacity(((2 * r + 1) * (2 * r + 1)) as usize);
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+            let scaled = distance * peaks.len() as f32;
+            let ring = scaled as usize;
+            let weight = if distance >= 1.0 || ring >= peaks.len() {
+                0.0
+            } else {
+                let x = scaled.fract();
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    peaks[ring] * (4.0 - 1.0 / (x * (1.0 - x))).exp()
+                }
+            };
+            kernel.push(weight);
+        }
+    }
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|k| *k /= total);
+    kernel
+}
+
+#[derive(Clone)]
+struct Spectrum {
+    width: usize,
+    height: usize,
+    kernel: Arc<[Complex<f32>]>,
+    rows: Arc<dyn Fft<f32>>,
+    columns: Arc<dyn Fft<f32>>,
+    inverse_rows: Arc<dyn Fft<f32>>,
+    inverse_columns: Arc<dyn Fft<f32>>,
+}
+
+impl Spectrum {
+    fn new(kernel: &[f32], radius: usize, width: usize, height: usize) -> Spectrum {
+        let mut planner = FftPlanner::new();
+        let mut spectrum = Spectrum {
+            width,
+            height,
+            kernel: Arc::new([]),
+            rows: planner.plan_fft_forward(width),
+            columns: planner.plan_fft_forward(height),
+            inverse_rows: planner.plan_fft_inverse(width),
+            inverse_columns: planner.plan_fft_inverse(height),
+        };
+        // the kernel is centred on the origin and wrapped onto the torus
+        let mut wrapped = vec![Complex::new(0.0, 0.0); width * height];
+        let size = 2 * radius + 1;
+        for (i, &k) in kernel.iter().enumerate() {
+            let dx = (i % size) as i64 - radius as i64;
+            let dy = (i / size) as i64 - radius as i64;
+            let x = dx.rem_euclid(width as i64) as usize;
+            let y = dy.rem_euclid(height as i64) as usize;
+            wrapped[y * width + x].re += k;
+        }
+        spectrum.transform(&mut wrapped, false);
+        spectrum.kernel = wrapped.into();
+        spectrum
+    }
+
+    fn transform(&self, buffer: &mut [Complex<f32>], inverse: bool) {
+        let (rows, columns) = if inverse {
+            (&self.inverse_rows, &self.inverse_columns)
+        } else {
+            (&self.rows, &self.columns)
+        };
+        rows.process(buffer);
+        let mut transposed = transpose(buffer, self.width, self.height);
+        columns.process(&mut transposed);
+        buffer.copy_from_slice(&transpose(&transposed, self.height, self.width));
+    }
+
+    fn convolve(&self, buffer: &mut [Complex<f32>]) {
+        self.transform(buffer, false);
+        let scale = 1.0 / (self.width * self.height) as f32;
+        buffer
+            .iter_mut()
+            .zip(self.kernel.iter())
+            .for_each(|(b, k)| *b = *b * k * scale);
+        self.transform(buffer, true);
+    }
+}
+
+fn transpose(buffer: &[Complex<f32>], width: usize, height: usize) -> Vec<Complex<f32>> {
+    let mut out = vec![Complex::new(0.0, 0.0); buffer.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[x * height + y] = buffer[y * width + x];
+        }
+    }
+    out
+}
+
+/// A known Lenia creature together with the rule it lives in. Others can be
+/// added to `CREATURES` the same way from their published kernels, growth
+/// parameters and cells.
+pub struct Creature {
+    pub name: &'static str,
+    pub radius: u16,
+    pub steps_per_unit: u16,
+    pub mu: f32,
+    pub sigma: f32,
+    pub peaks: &'static [f32],
+    pub cells: &'static [&'static [f32]],
+}
+
+impl Creature {
+    pub fn rule(&self) -> Lenia {
+        Lenia::new(
+            self.radius,
+            self.peaks,
+            self.mu,
+            self.sigma,
+            1.0 / self.steps_per_unit as f32,
+        )
+        .unwrap()
+    }
+
+    /// An otherwise empty field of `size` with the creature's top-left
+    /// corner at `at`.
+    pub fn data(&self, size: (u16, u16), at: (u16, u16)) -> Vec<LeniaCell> {
+        let (width, height) = (size.0 as usize, size.1 as usize);
+        let mut data = vec![LeniaCell { value: 0.0 }; width * height];
+        for (dy, row) in self.cells.iter().enumerate() {
+            for (dx, &value) in row.iter().enumerate() {
+                let x = (at.0 as usize + dx) % width;
+                let y = (at.1 as usize + dy) % height;
+                data[y * width + x] = LeniaCell::from(value);
+            }
+        }
+        data
+    }
+}
+
+/// Every bundled creature.
+pub const CREATURES: &[Creature] = &[ORBIUM];
+
+pub const ORBIUM: Creature = Creature {
+    name: "Orbium unicaudatus",
+    radius: 13,
+    steps_per_unit: 10,
+    mu: 0.15,
+    sigma: 0.015,
+    peaks: &[1.0],
+    cells: &[
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.1, 0.14, 0.1, 0.0, 0.0, 0.03, 0.03, 0.0, 0.0, 0.3, 0.0,
+            0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.24, 0.3, 0.3, 0.18, 0.14, 0.15, 0.16, 0.15, 0.09, 0.2,
+            0.0, 0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.15, 0.34, 0.44, 0.46, 0.38, 0.18, 0.14, 0.11, 0.13, 0.19,
+            0.18, 0.45, 0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.06, 0.13, 0.39, 0.5, 0.5, 0.37, 0.06, 0.0, 0.0, 0.0, 0.02, 0.16,
+            0.68, 0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.11, 0.17, 0.17, 0.33, 0.4, 0.38, 0.28, 0.14, 0.0, 0.0, 0.0, 0.0, 0.0,
+            0.18, 0.42, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.09, 0.18, 0.13, 0.06, 0.08, 0.26, 0.32, 0.32, 0.27, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.82, 0.0, 0.0,
+        ],
+        &[
+            0.27, 0.0, 0.16, 0.12, 0.0, 0.0, 0.0, 0.25, 0.38, 0.44, 0.45, 0.34, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.22, 0.17, 0.0,
+        ],
+        &[
+            0.0, 0.07, 0.2, 0.02, 0.0, 0.0, 0.0, 0.31, 0.48, 0.57, 0.6, 0.57, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.49, 0.0,
+        ],
+        &[
+            0.0, 0.59, 0.19, 0.0, 0.0, 0.0, 0.0, 0.2, 0.57, 0.69, 0.76, 0.76, 0.49, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.36, 0.0,
+        ],
+        &[
+            0.0, 0.58, 0.19, 0.0, 0.0, 0.0, 0.0, 0.0, 0.67, 0.83, 0.9, 0.92, 0.87, 0.12, 0.0, 0.0,
+            0.0, 0.0, 0.22, 0.07,
+        ],
+        &[
+            0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.7, 0.93, 1.0, 1.0, 1.0, 0.61, 0.0, 0.0, 0.0,
+            0.0, 0.18, 0.11,
+        ],
+        &[
+            0.0, 0.0, 0.82, 0.0, 0.0, 0.0, 0.0, 0.0, 0.47, 1.0, 1.0, 0.98, 1.0, 0.96, 0.27, 0.0,
+            0.0, 0.0, 0.19, 0.1,
+        ],
+        &[
+            0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 1.0, 1.0, 0.84, 0.92, 0.97, 0.54, 0.14,
+            0.04, 0.1, 0.21, 0.05,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0, 0.09, 0.8, 1.0, 0.82, 0.8, 0.85, 0.63, 0.31,
+            0.18, 0.19, 0.2, 0.01,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.36, 0.1, 0.0, 0.0, 0.0, 0.05, 0.54, 0.86, 0.79, 0.74, 0.72, 0.6, 0.39,
+            0.28, 0.24, 0.13, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.01, 0.3, 0.07, 0.0, 0.0, 0.08, 0.36, 0.64, 0.7, 0.64, 0.6, 0.51, 0.39,
+            0.29, 0.19, 0.04, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.1, 0.24, 0.14, 0.1, 0.15, 0.29, 0.45, 0.53, 0.52, 0.46, 0.4,
+            0.31, 0.21, 0.08, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.21, 0.21, 0.22, 0.29, 0.36, 0.39, 0.37, 0.33, 0.26,
+            0.18, 0.09, 0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.03, 0.13, 0.19, 0.22, 0.24, 0.24, 0.23, 0.18, 0.13,
+            0.05, 0.0, 0.0, 0.0, 0.0,
+        ],
+        &[
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.02, 0.06, 0.08, 0.09, 0.07, 0.05, 0.01, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+        ],
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn mass(data: &[LeniaCell]) -> f32 {
+        data.iter().map(|c| c.value()).sum()
+    }
+
+    #[test]
+    fn orbium_keeps_its_mass() {
+        let rule = ORBIUM.rule();
+        let mut data = ORBIUM.data((64, 64), (20, 20));
+        for generation in 0..20 {
+            let grid = Grid::init_with_data(data, 64).unwrap();
+            data = rule.next_generation(&grid, generation).unwrap();
+        }
+        let start = mass(&data);
+        for generation in 20..120 {
+            let grid = Grid::init_with_data(data, 64).unwrap();
+            data = rule.next_generation(&grid, generation).unwrap();
+        }
+        assert!((mass(&data) - start).abs() < start * 0.05);
+    }
+
+    #[test]
+    fn fft_matches_direct_convolution() {
+        let rule = Lenia::new(FFT_RADIUS, &[0.5, 1.0], 0.2, 0.03, 0.1).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let data = (0..24 * 20).map(|_| LeniaCell::rnd(&mut rng)).collect();
+        let grid = Grid::init_with_data(data, 24).unwrap();
+        let fft = rule.next_generation(&grid, 0).unwrap();
+        for (i, cell) in fft.iter().enumerate() {
+            let position = ((i % 24) as i32, (i / 24) as i32);
+            let direct = rule.next(&grid.get_area(position, rule.source_size()));
+            assert!((cell.value() - direct.value()).abs() < 1e-4, "cell {}", i);
+        }
+    }
+}
@@ -1,15 +1,62 @@
 extern crate core;
 
+use rand::Rng;
+
+pub use crate::cell_rng::CellRng;
 pub use crate::error_handling::GError;
 pub use crate::error_handling::GResult;
 pub use crate::timer::Timer;
 
+pub use crate::controls::{Action, Bindings, Controller, Key, KeyPress, DEFAULT_BINDINGS};
+pub use crate::cyclic::{Cyclic, CyclicCell, CYCLIC_PRESETS};
+pub use crate::elementary::Elementary;
+pub use crate::forest_fire::{ForestCell, ForestFire};
 pub use crate::game::Game;
-
+pub use crate::gray_scott::{ChemicalData, GrayScott};
+pub use crate::grid::Grid;
+pub use crate::isotropic_rules::{IsotropicRule, LifeCell};
+pub use crate::larger_than_life::{LargerThanLife, LtlCell};
+pub use crate::layers::Layered;
+#[cfg(feature = "lenia")]
+pub use crate::lenia::{Creature, Lenia, LeniaCell, CREATURES, ORBIUM};
+pub use crate::life3d::Life3D;
+pub use crate::margolus::{BlockRule, BlockTable, Margolus, MARGOLUS_PRESETS};
+pub use crate::pacing::{FrameLimiter, DEFAULT_FPS};
+pub use crate::pattern::Pattern;
+pub use crate::rule_table::{Neighborhood, RuleTable, TableState};
+pub use crate::sandpile::{Avalanche, AvalancheStats, GrainDropper, SandCell, Sandpile, Toppling};
+pub use crate::schedule::Schedule;
+pub use crate::topology::{Cubic, Hexagonal, Square, TextLayout, Topology, Triangular};
+pub use crate::triangular_life::TriangularLife;
+pub use crate::turmite::{Turmite, TurmiteCell, TurmiteWorld, Turn};
+pub use crate::viewport::Viewport;
+pub use crate::wireworld::{WireCell, WireWorld, EXAMPLES};
+
+mod cell_rng;
+mod controls;
+mod cyclic;
+mod elementary;
 mod error_handling;
+mod forest_fire;
 mod game;
+mod gray_scott;
 mod grid;
+mod isotropic_rules;
+mod larger_than_life;
+mod layers;
+mod life3d;
+mod margolus;
+mod pacing;
+mod pattern;
+mod rule_table;
+mod sandpile;
+mod schedule;
 mod timer;
+mod topology;
+mod triangular_life;
+mod turmite;
+mod viewport;
+mod wireworld;
 
 #[cfg(feature = "graphics-ggez")]
 mod ggez_graphics;
@@ -17,6 +64,9 @@ mod ggez_graphics;
 #[cfg(feature = "graphics-piston")]
 mod piston_graphics;
 
+#[cfg(feature = "lenia")]
+mod lenia;
+
 #[cfg(feature = "graphics-pixels")]
 mod pixels_graphics;
 
@@ -35,6 +85,7 @@ type IndexType = (i32, i32);
 
 pub type Color = (u8, u8, u8, u8);
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Size {
     pub width: u16,
     pub height: u16,
@@ -43,13 +94,85 @@ pub struct Size {
 pub trait RuleSet: Clone + Send + Sync + 'static {
     type Data: DataType;
     const SOURCE_SIZE: u8;
-    fn next(source: &[&Self::Data]) -> Self::Data;
+    fn next(&self, source: &[&Self::Data]) -> Self::Data;
+
+    /// Like `next`, with a random stream that is fixed by the game seed, the
+    /// generation and the cell position. Stochastic rules override this.
+    fn next_with_rng(&self, source: &[&Self::Data], _rng: &mut CellRng) -> Self::Data {
+        self.next(source)
+    }
+
+    /// Width of the square window passed to `next`. Rules whose reach is only
+    /// known at runtime override this.
+    fn source_size(&self) -> u8 {
+        Self::SOURCE_SIZE
+    }
+
+    /// Computes a whole generation at once, for rules that are faster with a
+    /// global view of the grid or that alternate between phases. `generation`
+    /// counts the steps taken so far. Returning `None` makes `Game` call
+    /// `next` for every cell.
+    fn next_generation(
+        &self,
+        _grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        None
+    }
+
+    /// Whether `next` gives the new state of a cell on its own, so that the
+    /// in-place schedules such as `Schedule::Checkerboard` can update cells
+    /// one at a time. Rules that only work through `next_generation`, such
+    /// as block rules or rules moving things between cells, return false.
+    fn supports_cell_updates(&self) -> bool {
+        true
+    }
+
+    /// Rejects topologies whose windows the rule cannot read, for rules that
+    /// expect the neighbours of one particular tiling.
+    fn check_topology(&self, _topology: &dyn Topology) -> GResult<()> {
+        Ok(())
+    }
+
+    /// States that can be painted onto the grid by hand, starting with the
+    /// empty one used to erase and clear. Rules that return none cannot be
+    /// edited.
+    fn palette(&self) -> Vec<Self::Data> {
+        Vec::new()
+    }
+}
+
+/// Something that moves over the grid and changes cells, such as a
+/// Langton's ant. Agents are stepped by `Game::next_step` after the cells.
+pub trait Agent<D: DataType>: Send + 'static {
+    fn step(&mut self, grid: &mut Grid<D>);
+    fn position(&self) -> IndexType;
+    fn get_color(&self) -> Color;
 }
 
 pub trait DataType: Clone + Send + Sync + 'static {}
 
 pub trait ColoredDataType: DataType {
     fn get_color(&self) -> Color;
+
+    /// Number of fields stacked in the cell, see `Layered`.
+    fn layer_count() -> usize {
+        1
+    }
+
+    /// Names of the layers from the top. A plain cell is one layer named
+    /// after its type.
+    fn layer_names(&self) -> Vec<&'static str> {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        vec![name.rsplit("::").next().unwrap_or(name)]
+    }
+
+    /// Colour of layer `layer` on its own, where `get_color` shows all of
+    /// them.
+    fn get_layer_color(&self, _layer: usize) -> Color {
+        self.get_color()
+    }
 }
 
 pub trait PrintableDataType: DataType {
@@ -57,5 +180,5 @@ pub trait PrintableDataType: DataType {
 }
 
 pub trait RandomInit {
-    fn rnd() -> Self;
+    fn rnd(rng: &mut impl Rng) -> Self;
 }
@@ -0,0 +1,134 @@
+use std::any::Any;
+use std::ops::RangeInclusive;
+
+use crate::{Cubic, GError, GResult, LifeCell, RuleSet, Topology};
+
+/// Three-dimensional Life for the `Cubic` topology in Bays' notation
+/// `ElEuFlFu`: a live cell survives with between `El` and `Eu` live
+/// neighbours and a dead one comes alive with between `Fl` and `Fu`.
+#[derive(Clone)]
+pub struct Life3D {
+    survival: RangeInclusive<usize>,
+    birth: RangeInclusive<usize>,
+}
+
+impl Default for Life3D {
+    fn default() -> Self {
+        Life3D::parse("4555").unwrap()
+    }
+}
+
+impl Life3D {
+    /// Reads `4555` or `5766`, one digit per bound, or the bounds separated
+    /// by commas or slashes when some of them are above 9, as in
+    /// `10,21,10,21`.
+    pub fn parse(rule: &str) -> GResult<Life3D> {
+        let err = |message: &str| GError::RuleStringError {
+            rule: rule.to_string(),
+            message: message.to_string(),
+        };
+        let rule = rule.trim();
+        let bounds: Vec<&str> = if rule.contains([',', '/']) {
+            rule.split([',', '/']).map(str::trim).collect()
+        } else {
+            rule.char_indices()
+                .map(|(i, c)| &rule[i..i + c.len_utf8()])
+                .collect()
+        };
+        let bounds = bounds
+            .iter()
+            .map(|b| b.parse::<usize>().map_err(|_| err("expected a number")))
+            .collect::<GResult<Vec<_>>>()?;
+        match bounds[..] {
+            [el, eu, fl, fu] if el <= eu && fl <= fu && eu <= 26 && fu <= 26 => Ok(Life3D {
+                survival: el..=eu,
+                birth: fl..=fu,
+            }),
+            [_, _, _, _] => Err(err("bounds must be ordered and at most 26")),
+            _ => Err(err("expected four bounds")),
+        }
+    }
+}
+
+impl RuleSet for Life3D {
+    type Data = LifeCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let alive = source[1..].iter().filter(|c| c.is_alive()).count();
+        let range = if source[0].is_alive() {
+            &self.survival
+        } else {
+            &self.birth
+        };
+        LifeCell::from(range.contains(&alive))
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![LifeCell::from(false), LifeCell::from(true)]
+    }
+
+    fn check_topology(&self, topology: &dyn Topology) -> GResult<()> {
+        let topology: &dyn Any = topology;
+        if topology.downcast_ref::<Cubic>().is_none() {
+            return Err(GError::TopologyError(
+                "3D Life needs a Cubic topology".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Neighborhood, Triangular};
+
+    #[test]
+    fn blinker_4555() {
+        // Seven cells that mirror themselves across the x = y plane every
+        // generation.
+        let cubic = Cubic::new((8, 8, 8), Neighborhood::Moore).unwrap();
+        let (width, height) = cubic.grid_size();
+        let data = vec![LifeCell::from(false); width as usize * height as usize];
+        let mut game = Game::init_with_rules(Life3D::parse("4555").unwrap(), data, width)
+            .unwrap()
+            .with_topology(cubic)
+            .unwrap();
+        let mut phase = vec![
+            (2, 3, 2),
+            (2, 3, 3),
+            (3, 2, 3),
+            (3, 3, 4),
+            (3, 4, 3),
+            (4, 3, 2),
+            (4, 3, 3),
+        ];
+        for &(x, y, z) in &phase {
+            game.toggle(cubic.index(x, y, z));
+        }
+        for _ in 0..4 {
+            game.next_step();
+            phase = phase.iter().map(|&(x, y, z)| (y, x, z)).collect();
+            phase.sort();
+            let mut alive: Vec<(i32, i32, i32)> = game
+                .into_iter()
+                .filter(|(_, cell)| cell.is_alive())
+                .map(|(index, _)| cubic.position(index))
+                .collect();
+            alive.sort();
+            assert_eq!(alive, phase);
+        }
+    }
+
+    #[test]
+    fn needs_a_cubic_grid() {
+        let rule = Life3D::default();
+        assert!(rule.check_topology(&Triangular::Moore).is_err());
+        let cubic = Cubic::new((4, 4, 4), Neighborhood::Moore).unwrap();
+        assert!(rule.check_topology(&cubic).is_ok());
+        let data = vec![LifeCell::from(false); 64];
+        let game = Game::init_with_rules(rule, data, 8).unwrap();
+        assert!(game.with_topology(Triangular::Moore).is_err());
+    }
+}
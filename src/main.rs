@@ -1,11 +1,9 @@
 mod conway_rules;
 mod heat_rules;
-mod rgb;
 
-use conway_rules::{BoolData, ClassicConway, ConwayColors, ConwayWithHistory};
-use game_of_life::{GResult, Game};
-use heat_rules::HeatRules;
-use rgb::RGBRules;
+use conway_rules::{BoolData, ClassicConway, ConwayWithHistory};
+use game_of_life::{Bindings, ColoredDataType, GResult, Game, RuleSet};
+use heat_rules::HeatedConway;
 
 //**************************************************************
 
@@ -24,18 +22,51 @@ type BackEnd = game_of_life::backends::GgezBackend;
 #[cfg(feature = "graphics-piston")]
 type BackEnd = game_of_life::backends::PistonBackend;
 
+const WIDTH: u16 = 320;
+const HEIGHT: u16 = 160;
+const SIZE: (u16, u16) = (WIDTH, HEIGHT);
+/// Key bindings replacing the defaults, in the format of `DEFAULT_BINDINGS`.
+const BINDINGS_FILE: &str = "bindings.txt";
+
 fn main() -> GResult<()> {
     //use game_of_life::RandomInit;
 
-    const WIDTH: u16 = 320;
-    const HEIGHT: u16 = 160;
-    const SIZE: (u16, u16) = (WIDTH, HEIGHT);
-
-    //let v:Vec<BoolData> = (0..600).map(|_|BoolData::rnd()).collect();
+    //let v:Vec<BoolData> = (0..600).map(|_|BoolData::rnd(&mut rand::thread_rng())).collect();
 
     //let mut game: Game<ClassicConway>  = Game::init_with_data(v,333).context("Data is wrong size")?;
 
-    let mut game: Game<ConwayWithHistory> = Game::init_random_data(SIZE)?;
+    // Usage: game_of_life [seed] [density | heat]. A density switches to
+    // plain Conway with that share of live cells and `heat` to Conway on a
+    // temperature layer.
+    let mut args = std::env::args().skip(1);
+    let seed = args
+        .next()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(rand::random);
+    let mode = args.next();
+    let density = mode.as_deref().and_then(|d| d.parse::<f64>().ok());
+    println!("seed: {}", seed);
+
+    if mode.as_deref() == Some("heat") {
+        let game = Game::init_random_data_with_rules_seeded(HeatedConway::default(), SIZE, seed)?;
+        return run(game);
+    }
+    match density {
+        Some(density) => run(Game::init_generated_data(ClassicConway {}, SIZE, seed, |rng| {
+            BoolData::rnd_with_density(rng, density)
+        })?),
+        None => run(Game::<ConwayWithHistory>::init_random_data_seeded(SIZE, seed)?),
+    }
+}
+
+fn run<R>(mut game: Game<R>) -> GResult<()>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    if std::path::Path::new(BINDINGS_FILE).exists() {
+        game = game.with_bindings(Bindings::from_file(BINDINGS_FILE)?);
+    }
 
     #[cfg(not(feature = "graphics-ggez"))]
     let return_value = game.run::<BackEnd>((WIDTH as u32 * 4, HEIGHT as u32 * 4));
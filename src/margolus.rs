@@ -0,0 +1,233 @@
+use crate::{DataType, GError, GResult, Grid, LifeCell, RuleSet};
+
+/// Block rules in MCell's `MS,D` notation, read by `BlockTable::parse`.
+pub const MARGOLUS_PRESETS: &[(&str, &str)] = &[
+    ("bbm", "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15"),
+    ("critters", "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0"),
+    ("sand", "MS,D0;4;8;12;4;12;12;13;8;12;12;14;12;13;14;15"),
+    ("tron", "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0"),
+];
+
+/// A rule that replaces a 2x2 block of cells by a new block. Blocks are
+/// given as top-left, top-right, bottom-left and bottom-right.
+pub trait BlockRule: Clone + Send + Sync + 'static {
+    type Data: DataType;
+
+    /// `odd` is set on the generations where the block grid is shifted, for
+    /// rules that treat the two phases differently.
+    fn next_block(&self, block: [&Self::Data; 4], odd: bool) -> [Self::Data; 4];
+
+    /// See `RuleSet::palette`.
+    fn palette(&self) -> Vec<Self::Data> {
+        Vec::new()
+    }
+}
+
+/// Runs a `BlockRule` on the Margolus neighbourhood: even generations split
+/// the grid into blocks starting at `(0, 0)` and odd ones into blocks
+/// starting at `(1, 1)`, wrapping around the edges. Grids should have an
+/// even width and height; otherwise the row and column left over in each
+/// phase are not updated.
+#[derive(Clone)]
+pub struct Margolus<B> {
+    rule: B,
+}
+
+impl<B: BlockRule + Default> Default for Margolus<B> {
+    fn default() -> Self {
+        Margolus::new(B::default())
+    }
+}
+
+impl<B: BlockRule> Margolus<B> {
+    pub fn new(rule: B) -> Margolus<B> {
+        Margolus { rule }
+    }
+
+    pub fn get_rule(&self) -> &B {
+        &self.rule
+    }
+}
+
+impl Margolus<BlockTable> {
+    pub fn preset(name: &str) -> Option<Margolus<BlockTable>> {
+        BlockTable::preset(name).map(Margolus::new)
+    }
+}
+
+impl<B: BlockRule> RuleSet for Margolus<B> {
+    type Data = B::Data;
+    const SOURCE_SIZE: u8 = 1;
+
+    /// Blocks cannot be updated one cell at a time, so cells are left as
+    /// they are; `next_generation` does the work.
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        source[0].clone()
+    }
+
+    fn next_generation(&self, grid: &Grid<Self::Data>, generation: u64) -> Option<Vec<Self::Data>> {
+        let size = grid.get_size();
+        let (w, h) = (size.width as usize, size.height as usize);
+        let data = grid.get_raw_data();
+        let mut next = data.to_vec();
+        let odd = generation % 2 == 1;
+        let offset = odd as usize;
+        for by in 0..h / 2 {
+            let rows = [by * 2 + offset, (by * 2 + offset + 1) % h];
+            for bx in 0..w / 2 {
+                let columns = [bx * 2 + offset, (bx * 2 + offset + 1) % w];
+                let indices = [
+                    rows[0] * w + columns[0],
+                    rows[0] * w + columns[1],
+                    rows[1] * w + columns[0],
+                    rows[1] * w + columns[1],
+                ];
+                let block = self.rule.next_block(indices.map(|i| &data[i]), odd);
+                for (i, cell) in indices.into_iter().zip(block) {
+                    next[i] = cell;
+                }
+            }
+        }
+        Some(next)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        self.rule.palette()
+    }
+
+    fn supports_cell_updates(&self) -> bool {
+        false
+    }
+}
+
+/// A block rule for two-state cells given as the new block for each of the
+/// 16 possible blocks, numbering live cells top-left 1, top-right 2,
+/// bottom-left 4 and bottom-right 8.
+#[derive(Clone)]
+pub struct BlockTable {
+    table: [u8; 16],
+}
+
+impl Default for BlockTable {
+    fn default() -> Self {
+        BlockTable::preset("bbm").unwrap()
+    }
+}
+
+impl BlockTable {
+    pub fn new(table: [u8; 16]) -> GResult<BlockTable> {
+        if table.iter().any(|&b| b > 15) {
+            return Err(GError::RuleParameterError(
+                "blocks are numbered from 0 to 15".to_string(),
+            ));
+        }
+        Ok(BlockTable { table })
+    }
+
+    /// Reads MCell's notation, such as
+    /// `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15` for the billiard-ball
+    /// model. The `MS,D` prefix is optional.
+    pub fn parse(rule: &str) -> GResult<BlockTable> {
+        let err = |message: &str| GError::RuleStringError {
+            rule: rule.to_string(),
+            message: message.to_string(),
+        };
+        let trimmed = rule.trim();
+        let body = match trimmed.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("MS,D") => &trimmed[4..],
+            _ => trimmed,
+        };
+        let blocks = body
+            .split([';', ','])
+            .map(|b| b.trim().parse::<u8>().map_err(|_| err("expected a number")))
+            .collect::<GResult<Vec<_>>>()?;
+        let table: [u8; 16] = blocks.try_into().map_err(|_| err("expected 16 blocks"))?;
+        BlockTable::new(table).map_err(|_| err("blocks are numbered from 0 to 15"))
+    }
+
+    pub fn preset(name: &str) -> Option<BlockTable> {
+        MARGOLUS_PRESETS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, rule)| BlockTable::parse(rule).unwrap())
+    }
+
+    /// Whether every block has exactly one predecessor, so that the rule can
+    /// be run backwards.
+    pub fn is_reversible(&self) -> bool {
+        let mut seen = [false; 16];
+        for &b in self.table.iter() {
+            seen[b as usize] = true;
+        }
+        seen.iter().all(|&s| s)
+    }
+}
+
+impl BlockRule for BlockTable {
+    type Data = LifeCell;
+
+    fn next_block(&self, block: [&LifeCell; 4], _odd: bool) -> [LifeCell; 4] {
+        let index = block
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_alive())
+            .fold(0, |acc, (i, _)| acc | 1 << i);
+        let next = self.table[index];
+        [0, 1, 2, 3].map(|i| LifeCell::from(next >> i & 1 == 1))
+    }
+
+    fn palette(&self) -> Vec<LifeCell> {
+        vec![LifeCell::from(false), LifeCell::from(true)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_parse_with_or_without_prefix() {
+        for (name, rule) in MARGOLUS_PRESETS {
+            let table = BlockTable::parse(rule).unwrap();
+            let bare = BlockTable::parse(&rule[4..]).unwrap();
+            assert_eq!(table.table, bare.table, "{}", name);
+            assert_eq!(BlockTable::preset(name).unwrap().table, table.table);
+        }
+        let critters = BlockTable::parse("ms,d15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0").unwrap();
+        assert_eq!(
+            critters.table,
+            BlockTable::preset("critters").unwrap().table
+        );
+    }
+
+    #[test]
+    fn reversibility() {
+        assert!(BlockTable::preset("bbm").unwrap().is_reversible());
+        assert!(BlockTable::preset("critters").unwrap().is_reversible());
+        assert!(!BlockTable::preset("sand").unwrap().is_reversible());
+    }
+
+    #[test]
+    fn bad_tables_are_rejected() {
+        assert!(BlockTable::parse("MS,D0;1;2").is_err());
+        assert!(BlockTable::parse("MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;16").is_err());
+        assert!(BlockTable::parse("MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;x").is_err());
+    }
+
+    #[test]
+    fn phases_alternate() {
+        let rule = Margolus::preset("tron").unwrap();
+        let data = vec![LifeCell::from(false); 16];
+        let mut grid = Grid::init_with_data(data, 4).unwrap();
+        let even = rule.next_generation(&grid, 0).unwrap();
+        assert!(even.iter().all(LifeCell::is_alive));
+        // On odd generations the block at (1, 1) holds a single live cell and
+        // stays put, while the empty blocks wrapping around the edges fill.
+        let mut data = vec![LifeCell::from(false); 16];
+        data[5] = LifeCell::from(true);
+        grid = Grid::init_with_data(data, 4).unwrap();
+        let odd = rule.next_generation(&grid, 1).unwrap();
+        let alive: Vec<usize> = (0..16).filter(|&i| odd[i].is_alive()).collect();
+        assert_eq!(alive, [0, 1, 2, 3, 4, 5, 7, 8, 11, 12, 13, 14, 15]);
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+/// Frames per second backends draw at unless told otherwise.
+pub const DEFAULT_FPS: u32 = 60;
+
+/// Spaces frames evenly, for backends whose event loop does not. A frame
+/// that comes late moves the following ones back instead of being caught up
+/// on.
+#[derive(Clone, Debug)]
+pub struct FrameLimiter {
+    interval: Duration,
+    next: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(fps: u32) -> FrameLimiter {
+        FrameLimiter {
+            interval: Duration::from_secs(1) / fps.max(1),
+            next: Instant::now(),
+        }
+    }
+
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Time left until the next frame is due.
+    pub fn remaining(&self) -> Duration {
+        self.next.saturating_duration_since(Instant::now())
+    }
+
+    /// When the next frame is due.
+    pub fn next_frame(&self) -> Instant {
+        self.next
+    }
+
+    /// Whether the next frame is due, in which case it is started.
+    pub fn frame_due(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next {
+            return false;
+        }
+        self.next = (self.next + self.interval).max(now);
+        true
+    }
+
+    /// Sleeps until the next frame is due and starts it.
+    pub fn wait(&mut self) {
+        std::thread::sleep(self.remaining());
+        self.frame_due();
+    }
+}
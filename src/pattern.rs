@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use crate::{GError, GResult};
+
+/// A rectangle of cell states, numbered like the states of
+/// `RuleSet::palette`, that `Game::stamp` copies into a grid.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    width: u16,
+    height: u16,
+    states: Vec<u8>,
+}
+
+impl Pattern {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> GResult<Pattern> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| GError::FileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Pattern::parse(&text)
+    }
+
+    /// Reads Golly RLE when the text has an `x = ...` header, and otherwise
+    /// plain text where `.` is state 0, `O` or `*` state 1 and digits stand
+    /// for themselves. Lines starting with `#` or `!` are comments.
+    pub fn parse(text: &str) -> GResult<Pattern> {
+        let is_rle = text
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .is_some_and(|l| l.starts_with('x'));
+        let rows = if is_rle {
+            parse_rle(text)?
+        } else {
+            parse_text(text)?
+        };
+        Ok(Pattern::from_rows(rows))
+    }
+
+    fn from_rows(rows: Vec<Vec<u8>>) -> Pattern {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+        let mut states = Vec::with_capacity(width * height);
+        for mut row in rows {
+            row.resize(width, 0);
+            states.extend(row);
+        }
+        Pattern {
+            width: width as u16,
+            height: height as u16,
+            states,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn state(&self, x: u16, y: u16) -> u8 {
+        self.states[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn max_state(&self) -> u8 {
+        self.states.iter().copied().max().unwrap_or(0)
+    }
+}
+
+fn parse_text(text: &str) -> GResult<Vec<Vec<u8>>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.starts_with('#') && !l.starts_with('!'))
+        .map(|(i, l)| {
+            l.trim_end()
+                .chars()
+                .map(|c| match c {
+                    '.' | ' ' => Ok(0),
+                    'O' | 'o' | '*' => Ok(1),
+                    '0'..='9' => Ok(c as u8 - b'0'),
+                    c => Err(GError::PatternError(format!(
+                        "unexpected '{}' on line {}",
+                        c,
+                        i + 1
+                    ))),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Rows of states from Golly RLE: `b` and `.` are state 0, `o` state 1,
+/// `A` to `X` states 1 to 24 and `pA` to `yX` the states above.
+fn parse_rle(text: &str) -> GResult<Vec<Vec<u8>>> {
+    let mut rows = vec![Vec::new()];
+    let mut count = String::new();
+    let mut prefix = None;
+    let body = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.starts_with('#') && !l.starts_with('x'));
+    for (line, l) in body {
+        for c in l.chars() {
+            let run = count.parse::<usize>().unwrap_or(1);
+            let state = match (prefix.take(), c) {
+                (None, '0'..='9') => {
+                    count.push(c);
+                    continue;
+                }
+                (None, '!') => return Ok(rows),
+                (None, '$') => {
+                    rows.extend((0..run).map(|_| Vec::new()));
+                    count.clear();
+                    continue;
+                }
+                (None, 'p'..='y') => {
+                    prefix = Some(c as u8 - b'p' + 1);
+                    continue;
+                }
+                (None, '.' | 'b') => 0,
+                (None, 'o') => 1,
+                (None, c) if c.is_whitespace() => continue,
+                (high, 'A'..='X') => {
+                    let state = high.unwrap_or(0) as u16 * 24 + (c as u8 - b'A') as u16 + 1;
+                    u8::try_from(state).map_err(|_| {
+                        GError::PatternError(format!(
+                            "state {} on line {} is too high",
+                            state, line
+                        ))
+                    })?
+                }
+                (_, c) => {
+                    return Err(GError::PatternError(format!(
+                        "unexpected '{}' on line {}",
+                        c, line
+                    )))
+                }
+            };
+            rows.last_mut().unwrap().extend((0..run).map(|_| state));
+            count.clear();
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(pattern: &Pattern) -> Vec<Vec<u8>> {
+        (0..pattern.height())
+            .map(|y| (0..pattern.width()).map(|x| pattern.state(x, y)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn glider_rle() {
+        let glider = Pattern::parse("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+        assert_eq!(rows(&glider), [[0, 1, 0], [0, 0, 1], [1, 1, 1]]);
+    }
+
+    #[test]
+    fn multi_state_runs() {
+        let pattern = Pattern::parse("x = 5, y = 2\n2pA.B$\n3yA2X!").unwrap();
+        assert_eq!(rows(&pattern), [[25, 25, 0, 2, 0], [241, 241, 241, 24, 24]]);
+        assert_eq!(pattern.max_state(), 241);
+        assert!(Pattern::parse("x = 1, y = 1\nyX!").is_err());
+        assert!(Pattern::parse("x = 1, y = 1\npz!").is_err());
+    }
+
+    #[test]
+    fn blank_rows_and_line_breaks() {
+        let pattern = Pattern::parse("x = 2, y = 4\no$\n2$\nb\no!").unwrap();
+        assert_eq!(rows(&pattern), [[1, 0], [0, 0], [0, 0], [0, 1]]);
+    }
+
+    #[test]
+    fn plain_text() {
+        let pattern = Pattern::parse("!Name: Blinker\n.O.\n.*.\n.2\n").unwrap();
+        assert_eq!(rows(&pattern), [[0, 1, 0], [0, 1, 0], [0, 2, 0]]);
+        assert!(Pattern::parse(".X.\n").is_err());
+    }
+}
@@ -1,48 +1,66 @@
-use crate::{ColoredDataType, Game, RuleSet};
+use crate::{Action, ColoredDataType, Controller, Game, KeyPress, RuleSet, Viewport};
 use glutin_window::GlutinWindow;
 use graphics::{Image, Text};
 use image::RgbaImage;
 use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, Texture, TextureSettings};
+use piston::keyboard::ModifierKey;
 use piston::{
-    Button, ButtonEvent, ButtonState, EventSettings, Events, Key, RenderEvent, UpdateEvent,
-    WindowSettings,
+    AdvancedWindow, Button, ButtonEvent, ButtonState, EventSettings, Events, MouseButton,
+    MouseCursorEvent, MouseScrollEvent, RenderEvent, UpdateEvent, Window, WindowSettings,
 };
 use std::{collections::VecDeque, time::Instant};
 use thiserror::Error;
 
+const TITLE: &str = "Game";
+
 pub fn run<R>(window_size: (u32, u32), game: &mut Game<R>) -> Result<(), PistonError>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let mut window: GlutinWindow = WindowSettings::new("Game", window_size)
-        .exit_on_esc(true)
+    let mut controller = Controller::new(game.get_bindings().clone());
+    let title = controller.title(TITLE, game);
+    let mut window: GlutinWindow = WindowSettings::new(title, window_size)
+        .exit_on_esc(false)
         .resizable(false)
         .build()?;
 
     let mut gl_graph = GlGraphics::new(OpenGL::V3_3);
 
     let mut events = Events::new(EventSettings::new());
-    let image = Image::new().rect([0.0, 0.0, window_size.0 as f64, window_size.1 as f64]);
     let texture_settings = TextureSettings::new()
         .convert_gamma(false)
         .filter(Filter::Nearest);
     let text = Text::new_color([1.0, 0.0, 0.0, 1.0], 24);
     let ref mut glyphs = GlyphCache::new("sansation.ttf", (), texture_settings)?;
 
-    let mut pause = true;
-    let mut show_fps = false;
     let mut fps_counter = FpsCounter::new();
     let mut fps = 0;
     let mut texture = Texture::from_image(&game.to_rgba()?, &texture_settings);
+    let (_, image_size) = game.to_image();
+    let window_area = (window_size.0 as f32, window_size.1 as f32);
+    let mut viewport = Viewport::new(image_size, window_area);
+    let mut modifiers = ModifierKey::default();
+    let mut cursor = [0.0, 0.0];
+    let (mut painting, mut erasing, mut dragging) = (false, false, false);
 
     fps_counter.get();
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.render_args() {
+            controller.wait_for_frame();
+            let (x, y) = viewport.to_window((0.0, 0.0));
+            let zoom = viewport.get_zoom();
+            let image = Image::new().rect([
+                x as f64,
+                y as f64,
+                (image_size.width as f32 * zoom) as f64,
+                (image_size.height as f32 * zoom) as f64,
+            ]);
             gl_graph.draw(args.viewport(), |c, gl| {
+                graphics::clear([0.0, 0.0, 0.0, 1.0], gl);
                 texture.update(&game.to_rgba()?);
                 image.draw(&texture, &c.draw_state, c.transform, gl);
-                if show_fps {
+                if controller.show_fps() {
                     text.draw_pos(
                         &format!("{:.2}", fps),
                         [0.0, 24.0],
@@ -58,24 +76,51 @@ where
         };
         if let Some(_) = e.update_args() {
             fps = fps_counter.get();
-            if !pause {
-                game.next_step();
+            controller.update(game);
+        }
+        modifiers.event(&e);
+        if let Some(position) = e.mouse_cursor_args() {
+            // The middle button drags the view.
+            if dragging {
+                let (dx, dy) = (position[0] - cursor[0], position[1] - cursor[1]);
+                viewport.pan(dx as f32, dy as f32);
+            }
+            cursor = position;
+        }
+        if let Some([_, scroll]) = e.mouse_scroll_args() {
+            let anchor = (cursor[0] as f32, cursor[1] as f32);
+            if scroll > 0.0 {
+                viewport.zoom_in(anchor);
+            } else if scroll < 0.0 {
+                viewport.zoom_out(anchor);
             }
         }
         if let Some(args) = e.button_args() {
-            if args.state == ButtonState::Press {
-                match args.button {
-                    Button::Keyboard(Key::Space) => pause ^= true,
-                    Button::Keyboard(Key::Right) => {
-                        if pause {
-                            game.next_step()
-                        }
-                    }
-                    Button::Keyboard(Key::F) => show_fps ^= true,
-                    _ => (),
+            let pressed = args.state == ButtonState::Press;
+            match args.button {
+                Button::Mouse(MouseButton::Left) => painting = pressed,
+                Button::Mouse(MouseButton::Right) => erasing = pressed,
+                Button::Mouse(MouseButton::Middle) => dragging = pressed,
+                _ => (),
+            }
+            if let (ButtonState::Press, Button::Keyboard(key)) = (args.state, args.button) {
+                let shift = modifiers.contains(ModifierKey::SHIFT);
+                let press = KeyPress::from_code(&key, shift);
+                match press.and_then(|p| controller.press(p, game, &mut viewport)) {
+                    Some(Action::Quit) => window.set_should_close(true),
+                    Some(_) => window.set_title(controller.title(TITLE, game)),
+                    None => (),
                 }
             }
         }
+        if painting || erasing {
+            let (x, y) = viewport.to_image((cursor[0] as f32, cursor[1] as f32));
+            match game.pick(x, y) {
+                Some(cell) if painting => game.paint(cell),
+                Some(cell) => game.erase(cell),
+                None => (),
+            }
+        }
     }
     Ok(())
 }
@@ -90,7 +135,7 @@ where
     R::Data: ColoredDataType,
 {
     fn to_rgba(&self) -> Result<RgbaImage, PistonError> {
-        let (data, size) = self.to_raw_colors();
+        let (data, size) = self.to_image();
         RgbaImage::from_raw(size.width as u32, size.height as u32, data)
             .ok_or("Cannot create image".to_string().into())
     }
@@ -1,16 +1,18 @@
 use winit::dpi::LogicalSize;
 use winit::error::{EventLoopError, OsError};
-use winit::event::{Event, WindowEvent};
-use winit::event_loop::EventLoop;
-use winit::keyboard::KeyCode;
+use winit::event::{ElementState, Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::PhysicalKey;
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
 
 use pixels::{Pixels, SurfaceTexture};
 
-use crate::{ColoredDataType, Game, RuleSet};
+use crate::{Action, ColoredDataType, Controller, Game, KeyPress, RuleSet, Viewport};
 use thiserror::Error;
 
+const TITLE: &str = "GoL Pixels";
+
 pub fn run<R>(window_size: (u32, u32), game: &mut Game<R>) -> Result<(), PixelsError>
 where
     R: RuleSet,
@@ -18,22 +20,29 @@ where
 {
     let event_loop = EventLoop::new()?;
     let mut input = WinitInputHelper::new();
+    let mut controller = Controller::new(game.get_bindings().clone());
     let window = {
         let size = LogicalSize::new(window_size.0 as f64, window_size.1 as f64);
         WindowBuilder::new()
-            .with_title("GoL Pixels")
+            .with_title(controller.title(TITLE, game))
             .with_inner_size(size)
             .with_resizable(false)
             .build(&event_loop)?
     };
 
-    let mut pixels = {
+    // The pixel buffer matches the window and the viewport picks what part
+    // of the game image goes in it.
+    let (mut pixels, mut viewport) = {
         let window_size = window.inner_size();
-        let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        let size = game.get_size();
-        Pixels::new(size.width as u32, size.height as u32, surface_texture)?
+        let (width, height) = (window_size.width, window_size.height);
+        let surface_texture = SurfaceTexture::new(width, height, &window);
+        let (_, size) = game.to_image();
+        let viewport = Viewport::new(size, (width as f32, height as f32));
+        (Pixels::new(width, height, surface_texture)?, viewport)
     };
-    let mut is_paused = true;
+    // Keys pressed since the last input step, as the input helper only
+    // answers for keys it is asked about.
+    let mut pressed = Vec::new();
     let mut possible_error = None;
 
     event_loop.run(|event, elwt| match event {
@@ -41,7 +50,7 @@ where
             event: WindowEvent::RedrawRequested,
             ..
         } => {
-            pixels.frame_mut().copy_from_slice(&game.to_raw_colors().0);
+            viewport.render(&game.to_image().0, pixels.frame_mut());
             let error = pixels.render().err();
             if error.is_some() {
                 possible_error = error.map(|e| e.into());
@@ -54,25 +63,69 @@ where
             ..
         } => elwt.exit(),
         _ => {
-            if input.update(&event) {
-                if !is_paused {
-                    game.next_step();
-                }
-
-                if input.key_pressed(KeyCode::Escape)
-                    || input.close_requested()
-                    || input.destroyed()
+            if let Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { event: key, .. },
+                ..
+            } = &event
+            {
+                if let (ElementState::Pressed, false, PhysicalKey::Code(code)) =
+                    (key.state, key.repeat, key.physical_key)
                 {
+                    pressed.push(code);
+                }
+            }
+            if input.update(&event) {
+                if input.close_requested() || input.destroyed() {
                     elwt.exit();
                     return;
                 }
-                if input.key_pressed(KeyCode::Space) {
-                    is_paused ^= true;
+                for code in pressed.drain(..) {
+                    let press = KeyPress::from_code(&code, input.held_shift());
+                    match press.and_then(|p| controller.press(p, game, &mut viewport)) {
+                        Some(Action::Quit) => {
+                            elwt.exit();
+                            return;
+                        }
+                        Some(_) => window.set_title(&controller.title(TITLE, game)),
+                        None => {}
+                    }
+                }
+                // Left paints with the brush and right erases.
+                let pointer = input
+                    .cursor()
+                    .and_then(|cursor| pixels.window_pos_to_pixel(cursor).ok())
+                    .map(|(x, y)| (x as f32 + 0.5, y as f32 + 0.5));
+                let painting = input.mouse_held(0);
+                if painting || input.mouse_held(1) {
+                    let cell = pointer.and_then(|point| {
+                        let (x, y) = viewport.to_image(point);
+                        game.pick(x, y)
+                    });
+                    match cell {
+                        Some(cell) if painting => game.paint(cell),
+                        Some(cell) => game.erase(cell),
+                        None => {}
+                    }
+                }
+                // The wheel zooms around the pointer and the middle button
+                // drags the view.
+                let scroll = input.scroll_diff().1;
+                let anchor = pointer.unwrap_or(viewport.center());
+                if scroll > 0.0 {
+                    viewport.zoom_in(anchor);
+                } else if scroll < 0.0 {
+                    viewport.zoom_out(anchor);
+                }
+                if input.mouse_held(2) {
+                    let (dx, dy) = input.cursor_diff();
+                    viewport.pan(dx, dy);
                 }
-                if input.key_pressed(KeyCode::ArrowRight) && is_paused {
-                    game.next_step();
+                // The loop wakes up for the next frame even without events.
+                if controller.frame_due() {
+                    controller.update(game);
+                    window.request_redraw();
                 }
-                window.request_redraw();
+                elwt.set_control_flow(ControlFlow::WaitUntil(controller.next_frame()));
             }
         }
     })?;
@@ -0,0 +1,877 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, GError, GResult, PrintableDataType, RuleSet};
+
+type StateSet = [u64; 4];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Neighborhood {
+    Moore,
+    VonNeumann,
+    Hexagonal,
+    OneDimensional,
+}
+
+impl Neighborhood {
+    fn parse(name: &str) -> Option<Neighborhood> {
+        match name {
+            "Moore" => Some(Neighborhood::Moore),
+            "vonNeumann" => Some(Neighborhood::VonNeumann),
+            "hexagonal" => Some(Neighborhood::Hexagonal),
+            "oneDimensional" => Some(Neighborhood::OneDimensional),
+            _ => None,
+        }
+    }
+
+    /// Positions in the 3x3 source window in Golly's order: centre first,
+    /// then the neighbours clockwise starting from north.
+    fn window_indices(&self) -> &'static [usize] {
+        match self {
+            Neighborhood::Moore => &[4, 1, 2, 5, 8, 7, 6, 3, 0],
+            Neighborhood::VonNeumann => &[4, 1, 5, 7, 3],
+            Neighborhood::Hexagonal => &[4, 1, 5, 8, 7, 3, 0],
+            Neighborhood::OneDimensional => &[4, 3, 5],
+        }
+    }
+
+    fn neighbour_count(&self) -> usize {
+        self.window_indices().len() - 1
+    }
+
+    /// For each neighbour, the one it swaps with when the neighbourhood is
+    /// mirrored left to right. Hexagonal neighbourhoods are stored skewed in
+    /// the window, so they mirror about its diagonal instead.
+    fn reflection(&self) -> Vec<usize> {
+        let window = &self.window_indices()[1..];
+        window
+            .iter()
+            .map(|&p| {
+                let (x, y) = (p % 3, p / 3);
+                let mirrored = match self {
+                    Neighborhood::Hexagonal => x * 3 + y,
+                    _ => y * 3 + 2 - x,
+                };
+                window.iter().position(|&q| q == mirrored).unwrap()
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct TableState {
+    state: u8,
+    color: Color,
+}
+
+impl TableState {
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+}
+
+impl DataType for TableState {}
+
+impl ColoredDataType for TableState {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+impl PrintableDataType for TableState {
+    fn get_char(&self) -> char {
+        match self.state {
+            0 => '.',
+            s => char::from_digit(s as u32 % 36, 36).unwrap(),
+        }
+    }
+}
+
+/// Table-driven rule loaded from a Golly `.rule` file. The first matching
+/// transition wins; a cell without a matching transition keeps its state.
+#[derive(Clone)]
+pub struct RuleTable {
+    name: String,
+    n_states: usize,
+    neighborhood: Neighborhood,
+    colors: Arc<[Color]>,
+    words: usize,
+    // lut[(position * n_states + state) * words + w] has bit `r` set when
+    // transition `r` accepts `state` at `position`
+    lut: Arc<[u64]>,
+    outputs: Arc<[u8]>,
+    // the transitions of `symmetries:permute` tables, which do not use `lut`
+    counts: Option<Arc<[CountTransition]>>,
+}
+
+impl RuleTable {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> GResult<RuleTable> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| GError::FileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        RuleTable::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> GResult<RuleTable> {
+        let mut name = String::new();
+        let mut section = "";
+        let mut table = TableBuilder::default();
+        let mut colors = ColorBuilder::default();
+        let mut has_table = false;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_nr = i + 1;
+            let err = |message: String| GError::RuleParseError {
+                line: line_nr,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('@') {
+                let mut parts = header.split_whitespace();
+                section = match parts.next() {
+                    Some("RULE") => {
+                        name = parts.collect::<Vec<_>>().join(" ");
+                        "RULE"
+                    }
+                    Some("TABLE") => {
+                        has_table = true;
+                        "TABLE"
+                    }
+                    Some("COLORS") => "COLORS",
+                    _ => "",
+                };
+                continue;
+            }
+            match section {
+                "TABLE" => table.line(line).map_err(err)?,
+                "COLORS" => colors.line(line).map_err(err)?,
+                _ => {}
+            }
+        }
+
+        if !has_table {
+            return Err(GError::RuleParseError {
+                line: 0,
+                message: "no @TABLE section".to_string(),
+            });
+        }
+        let n_states = table.n_states.ok_or_else(|| GError::RuleParseError {
+            line: 0,
+            message: "n_states is missing".to_string(),
+        })?;
+        table
+            .finish_header()
+            .map_err(|message| GError::RuleParseError { line: 0, message })?;
+        Ok(table.build(name, colors.build(n_states)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.n_states
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Panics if `state` is not below `n_states`.
+    pub fn cell(&self, state: u8) -> TableState {
+        TableState {
+            state,
+            color: self.colors[state as usize],
+        }
+    }
+
+    pub fn random_data(&self, size: (u16, u16), rng: &mut impl Rng) -> Vec<TableState> {
+        (0..size.0 as usize * size.1 as usize)
+            .map(|_| self.cell(rng.gen_range(0..self.n_states) as u8))
+            .collect()
+    }
+
+    fn lut_index(&self, position: usize, state: u8) -> usize {
+        (position * self.n_states + state as usize) * self.words
+    }
+}
+
+impl RuleSet for RuleTable {
+    type Data = TableState;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let indices = self.neighborhood.window_indices();
+        if let Some(counts) = &self.counts {
+            let centre = source[indices[0]].state;
+            let mut neighbours = [0; 8];
+            for (n, &i) in neighbours.iter_mut().zip(&indices[1..]) {
+                *n = source[i].state;
+            }
+            let neighbours = &mut neighbours[..indices.len() - 1];
+            neighbours.sort_unstable();
+            return counts
+                .iter()
+                .find(|t| t.matches(centre, neighbours))
+                .map_or_else(|| source[4].clone(), |t| self.cell(t.output));
+        }
+        let mut offsets = [0; 9];
+        for (position, &i) in indices.iter().enumerate() {
+            offsets[position] = self.lut_index(position, source[i].state);
+        }
+        let offsets = &offsets[..indices.len()];
+        for w in 0..self.words {
+            let bits = offsets
+                .iter()
+                .fold(!0u64, |acc, offset| acc & self.lut[offset + w]);
+            if bits != 0 {
+                let transition = w * 64 + bits.trailing_zeros() as usize;
+                return self.cell(self.outputs[transition]);
+            }
+        }
+        source[4].clone()
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        (0..self.n_states()).map(|s| self.cell(s as u8)).collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Token {
+    State(u8),
+    Var(usize),
+}
+
+struct Transition {
+    inputs: Vec<StateSet>,
+    output: u8,
+}
+
+/// A transition of a `symmetries:permute` table. Only how many neighbours
+/// are in each state matters, so neighbours are matched as a multiset
+/// instead of expanding every order of them.
+struct CountTransition {
+    centre: StateSet,
+    // sorted, so that equal sets are next to each other
+    neighbours: Vec<StateSet>,
+    // the sorted neighbour states when each of them is a single state
+    exact: Option<Vec<u8>>,
+    output: u8,
+}
+
+impl CountTransition {
+    fn new(transition: &Transition) -> CountTransition {
+        let mut neighbours = transition.inputs[1..].to_vec();
+        neighbours.sort_unstable();
+        let single = |set: &StateSet| {
+            let count: u32 = set.iter().map(|w| w.count_ones()).sum();
+            (count == 1).then(|| (0..=255).find(|&s| contains(set, s)).unwrap())
+        };
+        let mut exact = neighbours.iter().map(single).collect::<Option<Vec<u8>>>();
+        if let Some(exact) = exact.as_mut() {
+            exact.sort_unstable();
+        }
+        CountTransition {
+            centre: transition.inputs[0],
+            neighbours,
+            exact,
+            output: transition.output,
+        }
+    }
+
+    /// `neighbours` must be sorted.
+    fn matches(&self, centre: u8, neighbours: &[u8]) -> bool {
+        if !contains(&self.centre, centre) {
+            return false;
+        }
+        match &self.exact {
+            Some(exact) => exact == neighbours,
+            None => assign(&self.neighbours, &mut [false; 8], neighbours),
+        }
+    }
+}
+
+/// Whether each of `states` can be given its own slot that accepts it.
+fn assign(slots: &[StateSet], used: &mut [bool; 8], states: &[u8]) -> bool {
+    let Some((&state, rest)) = states.split_first() else {
+        return true;
+    };
+    for i in 0..slots.len() {
+        // Trying one of several equal free slots is enough.
+        let repeat = i > 0 && !used[i - 1] && slots[i] == slots[i - 1];
+        if used[i] || repeat || !contains(&slots[i], state) {
+            continue;
+        }
+        used[i] = true;
+        if assign(slots, used, rest) {
+            return true;
+        }
+        used[i] = false;
+    }
+    false
+}
+
+struct TableBuilder {
+    n_states: Option<usize>,
+    neighborhood: Neighborhood,
+    // applied by `finish_header`, as it may come before the neighbourhood
+    symmetry_name: String,
+    header_done: bool,
+    symmetries: Vec<Vec<usize>>,
+    permute: bool,
+    var_names: HashMap<String, usize>,
+    var_values: Vec<Vec<u8>>,
+    transitions: Vec<Transition>,
+}
+
+impl Default for TableBuilder {
+    fn default() -> Self {
+        TableBuilder {
+            n_states: None,
+            neighborhood: Neighborhood::Moore,
+            symmetry_name: "none".to_string(),
+            header_done: false,
+            symmetries: vec![(0..8).collect()],
+            permute: false,
+            var_names: HashMap::new(),
+            var_values: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+}
+
+impl TableBuilder {
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        if let Some(value) = line.strip_prefix("n_states:") {
+            let n = value
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("bad n_states: {}", e))?;
+            if !(2..=256).contains(&n) {
+                return Err(format!("n_states must be in 2..=256, got {}", n));
+            }
+            self.n_states = Some(n);
+        } else if let Some(value) = line.strip_prefix("neighborhood:") {
+            self.check_header("neighborhood")?;
+            self.neighborhood = Neighborhood::parse(value.trim())
+                .ok_or_else(|| format!("unsupported neighborhood '{}'", value.trim()))?;
+        } else if let Some(value) = line.strip_prefix("symmetries:") {
+            self.check_header("symmetries")?;
+            self.symmetry_name = value.trim().to_string();
+        } else if let Some(var) = line.strip_prefix("var ") {
+            self.add_var(var)?;
+        } else {
+            self.finish_header()?;
+            self.add_transition(line)?;
+        }
+        Ok(())
+    }
+
+    fn check_header(&self, key: &str) -> Result<(), String> {
+        if self.header_done {
+            return Err(format!("{} must come before the transitions", key));
+        }
+        Ok(())
+    }
+
+    /// Applies the symmetries to the neighbourhood, whichever of the two
+    /// lines came first.
+    fn finish_header(&mut self) -> Result<(), String> {
+        if !self.header_done {
+            self.set_symmetries(&self.symmetry_name.clone())?;
+            self.header_done = true;
+        }
+        Ok(())
+    }
+
+    fn n_states(&self) -> Result<usize, String> {
+        self.n_states
+            .ok_or_else(|| "n_states must be given before variables and transitions".to_string())
+    }
+
+    fn set_symmetries(&mut self, name: &str) -> Result<(), String> {
+        let n = self.neighborhood.neighbour_count();
+        self.permute = false;
+        let (rotations, reflect) = match name {
+            "none" => (1, false),
+            "reflect" | "reflect_horizontal" => (1, true),
+            "permute" => {
+                self.permute = true;
+                (1, false)
+            }
+            _ => {
+                let rest = name
+                    .strip_prefix("rotate")
+                    .ok_or_else(|| format!("unknown symmetries '{}'", name))?;
+                let (count, reflect) = match rest.strip_suffix("reflect") {
+                    Some(count) => (count, true),
+                    None => (rest, false),
+                };
+                let count = count
+                    .parse::<usize>()
+                    .map_err(|_| format!("unknown symmetries '{}'", name))?;
+                (count, reflect)
+            }
+        };
+        if rotations == 0 || !n.is_multiple_of(rotations) {
+            return Err(format!(
+                "symmetries '{}' do not fit {:?}",
+                name, self.neighborhood
+            ));
+        }
+        let shift = n / rotations;
+        let reflection = self.neighborhood.reflection();
+        let mut perms = Vec::new();
+        for r in (0..n).step_by(shift) {
+            perms.push((0..n).map(|i| (i + r) % n).collect());
+            if reflect {
+                perms.push((0..n).map(|i| (reflection[i] + r) % n).collect());
+            }
+        }
+        self.symmetries = perms;
+        Ok(())
+    }
+
+    fn parse_token(&self, token: &str) -> Result<Token, String> {
+        if let Some(&var) = self.var_names.get(token) {
+            return Ok(Token::Var(var));
+        }
+        let state = token
+            .parse::<usize>()
+            .map_err(|_| format!("unknown variable '{}'", token))?;
+        if state >= self.n_states()? {
+            return Err(format!("state {} is not below n_states", state));
+        }
+        Ok(Token::State(state as u8))
+    }
+
+    fn add_var(&mut self, var: &str) -> Result<(), String> {
+        let (name, values) = var
+            .split_once('=')
+            .ok_or_else(|| format!("bad variable definition '{}'", var))?;
+        let name = name.trim();
+        let values = values.trim();
+        let values = values
+            .strip_prefix('{')
+            .and_then(|v| v.strip_suffix('}'))
+            .ok_or_else(|| format!("variable values must be in braces: '{}'", values))?;
+        let mut states = Vec::new();
+        for token in values.split(',').map(str::trim) {
+            let expanded = match self.parse_token(token)? {
+                Token::State(s) => vec![s],
+                Token::Var(v) => self.var_values[v].clone(),
+            };
+            for s in expanded {
+                if !states.contains(&s) {
+                    states.push(s);
+                }
+            }
+        }
+        self.var_names
+            .insert(name.to_string(), self.var_values.len());
+        self.var_values.push(states);
+        Ok(())
+    }
+
+    fn add_transition(&mut self, line: &str) -> Result<(), String> {
+        let raw: Vec<String> = if line.contains(',') {
+            line.split(',').map(|t| t.trim().to_string()).collect()
+        } else {
+            line.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(String::from)
+                .collect()
+        };
+        let n = self.neighborhood.neighbour_count();
+        if raw.len() != n + 2 {
+            return Err(format!(
+                "expected {} entries in transition, found {}",
+                n + 2,
+                raw.len()
+            ));
+        }
+        let tokens = raw
+            .iter()
+            .map(|t| self.parse_token(t))
+            .collect::<Result<Vec<_>, _>>()?;
+        let centre = tokens[0];
+        let output = tokens[n + 1];
+        if let Token::Var(v) = output {
+            if !tokens[..=n].contains(&output) {
+                return Err(format!(
+                    "output variable '{}' does not appear in the inputs",
+                    self.var_name(v)
+                ));
+            }
+        }
+
+        if self.permute {
+            self.expand(&tokens[..=n], output);
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        for neighbours in self.arrangements(&tokens[1..=n]) {
+            let mut inputs = Vec::with_capacity(n + 1);
+            inputs.push(centre);
+            inputs.extend(neighbours);
+            if seen.insert(inputs.clone()) {
+                self.expand(&inputs, output);
+            }
+        }
+        Ok(())
+    }
+
+    fn var_name(&self, var: usize) -> &str {
+        self.var_names
+            .iter()
+            .find(|(_, &v)| v == var)
+            .map_or("?", |(name, _)| name.as_str())
+    }
+
+    fn arrangements(&self, neighbours: &[Token]) -> Vec<Vec<Token>> {
+        self.symmetries
+            .iter()
+            .map(|perm| perm.iter().map(|&i| neighbours[i]).collect())
+            .collect()
+    }
+
+    /// Variables used more than once are bound: every occurrence takes the
+    /// same value, so each combination becomes its own transition.
+    fn expand(&mut self, inputs: &[Token], output: Token) {
+        let mut bound: Vec<usize> = Vec::new();
+        for (i, t) in inputs.iter().enumerate() {
+            if let Token::Var(v) = t {
+                if !bound.contains(v) && (inputs[i + 1..].contains(t) || output == *t) {
+                    bound.push(*v);
+                }
+            }
+        }
+
+        let mut choice = vec![0; bound.len()];
+        loop {
+            let value_of = |v: usize| {
+                bound
+                    .iter()
+                    .position(|&b| b == v)
+                    .map(|p| self.var_values[v][choice[p]])
+            };
+            let sets = inputs
+                .iter()
+                .map(|t| {
+                    let mut set = [0u64; 4];
+                    match *t {
+                        Token::State(s) => insert(&mut set, s),
+                        Token::Var(v) => match value_of(v) {
+                            Some(s) => insert(&mut set, s),
+                            None => self.var_values[v].iter().for_each(|&s| insert(&mut set, s)),
+                        },
+                    }
+                    set
+                })
+                .collect();
+            let output = match output {
+                Token::State(s) => s,
+                Token::Var(v) => value_of(v).unwrap(),
+            };
+            self.transitions.push(Transition {
+                inputs: sets,
+                output,
+            });
+
+            let mut k = 0;
+            while k < bound.len() {
+                choice[k] += 1;
+                if choice[k] < self.var_values[bound[k]].len() {
+                    break;
+                }
+                choice[k] = 0;
+                k += 1;
+            }
+            if k == bound.len() {
+                break;
+            }
+        }
+    }
+
+    fn build(self, name: String, colors: Vec<Color>) -> RuleTable {
+        let n_states = self.n_states.unwrap();
+        if self.permute {
+            return RuleTable {
+                name,
+                n_states,
+                neighborhood: self.neighborhood,
+                colors: colors.into(),
+                words: 0,
+                lut: Arc::new([]),
+                outputs: Arc::new([]),
+                counts: Some(self.transitions.iter().map(CountTransition::new).collect()),
+            };
+        }
+        let positions = self.neighborhood.window_indices().len();
+        let words = self.transitions.len().div_ceil(64).max(1);
+        let mut lut = vec![0u64; positions * n_states * words];
+        for (r, transition) in self.transitions.iter().enumerate() {
+            for (position, set) in transition.inputs.iter().enumerate() {
+                for state in 0..n_states {
+                    if contains(set, state as u8) {
+                        lut[(position * n_states + state) * words + r / 64] |= 1 << (r % 64);
+                    }
+                }
+            }
+        }
+        RuleTable {
+            name,
+            n_states,
+            neighborhood: self.neighborhood,
+            colors: colors.into(),
+            words,
+            lut: lut.into(),
+            outputs: self.transitions.iter().map(|t| t.output).collect(),
+            counts: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ColorBuilder {
+    explicit: Vec<(usize, Color)>,
+    gradient: Option<(Color, Color)>,
+}
+
+impl ColorBuilder {
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("bad colour line '{}': {}", line, e))?;
+        match numbers[..] {
+            [s, r, g, b] => self.explicit.push((s as usize, (r, g, b, 255))),
+            [r1, g1, b1, r2, g2, b2] => {
+                self.gradient = Some(((r1, g1, b1, 255), (r2, g2, b2, 255)))
+            }
+            _ => return Err(format!("bad colour line '{}'", line)),
+        }
+        Ok(())
+    }
+
+    /// Golly's defaults: dark grey background and a red to yellow gradient.
+    fn build(self, n_states: usize) -> Vec<Color> {
+        let (from, to) = self
+            .gradient
+            .unwrap_or(((255, 0, 0, 255), (255, 255, 0, 255)));
+        let mut colors = vec![(48, 48, 48, 255)];
+        for s in 1..n_states {
+            let t = if n_states > 2 {
+                (s - 1) as f32 / (n_states - 2) as f32
+            } else {
+                0.0
+            };
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            colors.push((mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2), 255));
+        }
+        for (s, c) in self.explicit {
+            if s < n_states {
+                colors[s] = c;
+            }
+        }
+        colors
+    }
+}
+
+fn insert(set: &mut StateSet, state: u8) {
+    set[state as usize / 64] |= 1 << (state % 64);
+}
+
+fn contains(set: &StateSet, state: u8) -> bool {
+    set[state as usize / 64] & (1 << (state % 64)) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Hexagonal, IndexType};
+
+    const LIFE: &str = "\
+@RULE Life
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+0,1,1,1,0,0,0,0,0,1
+1,1,1,0,0,0,0,0,0,1
+1,1,1,1,0,0,0,0,0,1
+1,a,b,c,d,e,f,g,h,0
+";
+
+    /// Hexagonal Life B2/S34.
+    const HEX_LIFE: &str = "\
+@TABLE
+n_states:2
+neighborhood:hexagonal
+symmetries:permute
+var a={0,1}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+0,1,1,0,0,0,0,1
+1,1,1,1,0,0,0,1
+1,1,1,1,1,0,0,1
+1,a,b,c,d,e,f,0
+";
+
+    /// The 3x3 source window whose cells are set in `bits`, in raster order.
+    fn window(rule: &RuleTable, bits: u16) -> Vec<TableState> {
+        (0..9).map(|i| rule.cell((bits >> i & 1) as u8)).collect()
+    }
+
+    fn next(rule: &RuleTable, bits: u16) -> u8 {
+        let window = window(rule, bits);
+        rule.next(&window.iter().collect::<Vec<_>>()).state()
+    }
+
+    #[test]
+    fn life_table_matches_life() {
+        let rule = RuleTable::parse(LIFE).unwrap();
+        assert_eq!(rule.name(), "Life");
+        for bits in 0..512u16 {
+            let count = (bits & !0b1_0000).count_ones();
+            let alive = bits & 0b1_0000 != 0;
+            let expected = count == 3 || alive && count == 2;
+            assert_eq!(next(&rule, bits), expected as u8, "window {:09b}", bits);
+        }
+    }
+
+    #[test]
+    fn symmetries_apply_in_either_header_order() {
+        let before = "@TABLE\nn_states:2\nsymmetries:rotate4\nneighborhood:vonNeumann\n";
+        let after = "@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:rotate4\n";
+        for header in [before, after] {
+            let rule = RuleTable::parse(&format!("{}0,1,0,0,0,1\n", header)).unwrap();
+            // North, east, south and west are bits 1, 5, 7 and 3.
+            for bit in [1, 5, 7, 3] {
+                assert_eq!(next(&rule, 1 << bit), 1, "{:?} bit {}", header, bit);
+            }
+            assert_eq!(next(&rule, 0), 0);
+        }
+        let hexagonal = "@TABLE\nn_states:2\nsymmetries:rotate6\nneighborhood:hexagonal\n";
+        assert!(RuleTable::parse(hexagonal).is_ok());
+        let late = "@TABLE\nn_states:2\n0,1,0,0,0,0,0,0,0,1\nsymmetries:rotate4\n";
+        assert!(RuleTable::parse(late).is_err());
+    }
+
+    #[test]
+    fn permute_counts_neighbour_states() {
+        let life = RuleTable::parse(LIFE).unwrap();
+        assert!(life.lut.is_empty());
+        assert_eq!(life.counts.as_ref().map(|c| c.len()), Some(4));
+
+        let rule = RuleTable::parse(
+            "@TABLE\nn_states:3\nneighborhood:Moore\nsymmetries:permute\nvar a={1,2}\n\
+             0,1,a,2,0,0,0,0,0,1\n",
+        )
+        .unwrap();
+        let next = |states: [u8; 9]| {
+            let window: Vec<TableState> = states.iter().map(|&s| rule.cell(s)).collect();
+            rule.next(&window.iter().collect::<Vec<_>>()).state()
+        };
+        assert_eq!(next([2, 0, 0, 0, 0, 1, 0, 2, 0]), 1);
+        assert_eq!(next([0, 1, 0, 1, 0, 0, 2, 0, 0]), 1);
+        assert_eq!(next([1, 1, 0, 1, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(next([2, 2, 0, 2, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(next([2, 1, 0, 2, 0, 0, 0, 0, 1]), 0);
+    }
+
+    #[test]
+    fn one_dimensional_reflection_swaps_sides() {
+        let rule = RuleTable::parse(
+            "@TABLE\nn_states:2\nneighborhood:oneDimensional\nsymmetries:reflect\n0,1,0,1\n",
+        )
+        .unwrap();
+        // West is bit 3 and east bit 5 of the window.
+        assert_eq!(next(&rule, 1 << 3), 1);
+        assert_eq!(next(&rule, 1 << 5), 1);
+        assert_eq!(next(&rule, 0), 0);
+        assert_eq!(next(&rule, 1 << 3 | 1 << 5), 0);
+    }
+
+    #[test]
+    fn reflections_are_involutions() {
+        for neighborhood in [
+            Neighborhood::Moore,
+            Neighborhood::VonNeumann,
+            Neighborhood::Hexagonal,
+            Neighborhood::OneDimensional,
+        ] {
+            let reflection = neighborhood.reflection();
+            assert_eq!(reflection.len(), neighborhood.neighbour_count());
+            for (i, &j) in reflection.iter().enumerate() {
+                assert_eq!(reflection[j], i, "{:?}", neighborhood);
+            }
+        }
+        assert_eq!(Neighborhood::OneDimensional.reflection(), vec![1, 0]);
+    }
+
+    #[test]
+    fn palette_covers_all_256_states() {
+        let rule = RuleTable::parse("@TABLE\nn_states:256\nneighborhood:vonNeumann\n").unwrap();
+        let palette = rule.palette();
+        assert_eq!(palette.len(), 256);
+        assert_eq!(palette[255].state(), 255);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let text = "@TABLE\nn_states:2\nneighborhood:Moore\n0,1,1\n";
+        match RuleTable::parse(text) {
+            Err(GError::RuleParseError { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("expected a parse error"),
+        }
+        assert!(RuleTable::parse("@RULE Empty\n").is_err());
+    }
+
+    #[test]
+    fn hexagonal_domino_oscillates() {
+        // Two touching hexagons share two neighbours, which are born as the
+        // pair dies and bring it back the generation after.
+        let rule = RuleTable::parse(HEX_LIFE).unwrap();
+        let data = vec![rule.cell(0); 64];
+        let mut game = Game::init_with_rules(rule.clone(), data, 8)
+            .unwrap()
+            .with_topology(Hexagonal)
+            .unwrap();
+        let domino = [(4, 4), (5, 4)];
+        for &c in &domino {
+            game.toggle(c);
+        }
+        let alive = |game: &Game<RuleTable>| -> Vec<IndexType> {
+            (0..8)
+                .flat_map(|y| (0..8).map(move |x| (x, y)))
+                .filter(|&c| game.get_view_cell(c).state() == 1)
+                .collect()
+        };
+        game.next_step();
+        assert_eq!(alive(&game), [(5, 3), (5, 5)]);
+        game.next_step();
+        assert_eq!(alive(&game), domino);
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+
+use crate::{
+    Agent, CellRng, Color, ColoredDataType, DataType, Grid, IndexType, PrintableDataType,
+    RandomInit, RuleSet,
+};
+
+const THRESHOLD: u32 = 4;
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SandCell {
+    grains: u32,
+}
+
+impl SandCell {
+    pub fn grains(&self) -> u32 {
+        self.grains
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.grains < THRESHOLD
+    }
+}
+
+impl From<u32> for SandCell {
+    fn from(grains: u32) -> Self {
+        SandCell { grains }
+    }
+}
+
+impl DataType for SandCell {}
+
+impl ColoredDataType for SandCell {
+    /// Stable heights 0 to 3 in the usual black, blue, yellow and red;
+    /// cells about to topple are white.
+    fn get_color(&self) -> Color {
+        match self.grains {
+            0 => (0, 0, 0, 255),
+            1 => (30, 80, 220, 255),
+            2 => (240, 200, 30, 255),
+            3 => (200, 30, 30, 255),
+            _ => (255, 255, 255, 255),
+        }
+    }
+}
+
+impl PrintableDataType for SandCell {
+    fn get_char(&self) -> char {
+        match self.grains {
+            0 => ' ',
+            g if g < THRESHOLD => char::from_digit(g, 10).unwrap(),
+            _ => '*',
+        }
+    }
+}
+
+impl RandomInit for SandCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        SandCell {
+            grains: rng.gen_range(0..THRESHOLD),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Toppling {
+    /// Each generation is one sweep in which every unstable cell topples as
+    /// many times as its grains allow, so avalanches can be watched as they
+    /// spread.
+    Sweep,
+    /// Each generation topples until the whole pile is stable again.
+    Relax,
+}
+
+/// One avalanche: `size` counts topplings, `duration` the synchronous
+/// sweeps it took and `area` the distinct cells that toppled.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Avalanche {
+    pub size: usize,
+    pub duration: usize,
+    pub area: usize,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct AvalancheStats {
+    avalanches: Vec<Avalanche>,
+    current: Option<Avalanche>,
+}
+
+impl AvalancheStats {
+    pub fn record(&mut self, avalanche: Avalanche) {
+        self.avalanches.push(avalanche);
+    }
+
+    pub fn avalanches(&self) -> &[Avalanche] {
+        &self.avalanches
+    }
+
+    /// The avalanche still spreading when the pile topples one sweep per
+    /// generation; it is recorded once the pile is stable again.
+    pub fn current(&self) -> Option<Avalanche> {
+        self.current
+    }
+
+    /// Number of grains added so far, including those that toppled nothing.
+    pub fn grains(&self) -> usize {
+        self.avalanches.len()
+    }
+
+    pub fn mean_size(&self) -> f64 {
+        if self.avalanches.is_empty() {
+            return 0.0;
+        }
+        self.avalanches.iter().map(|a| a.size as f64).sum::<f64>() / self.avalanches.len() as f64
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.avalanches.iter().map(|a| a.size).max().unwrap_or(0)
+    }
+
+    pub fn max_duration(&self) -> usize {
+        self.avalanches
+            .iter()
+            .map(|a| a.duration)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// `(size, count)` pairs for the non-empty avalanches, sorted by size,
+    /// for plotting the power-law distribution.
+    pub fn size_distribution(&self) -> Vec<(usize, usize)> {
+        let mut sizes: Vec<usize> = self
+            .avalanches
+            .iter()
+            .map(|a| a.size)
+            .filter(|&s| s > 0)
+            .collect();
+        sizes.sort_unstable();
+        let mut distribution: Vec<(usize, usize)> = Vec::new();
+        for size in sizes {
+            match distribution.last_mut() {
+                Some((s, count)) if *s == size => *count += 1,
+                _ => distribution.push((size, 1)),
+            }
+        }
+        distribution
+    }
+}
+
+/// The Abelian sandpile: a cell holding four or more grains topples and
+/// passes one grain to each von Neumann neighbour. Grains falling off the
+/// edge of the grid are lost.
+#[derive(Clone)]
+pub struct Sandpile {
+    toppling: Toppling,
+}
+
+impl Default for Sandpile {
+    fn default() -> Self {
+        Sandpile::new(Toppling::Sweep)
+    }
+}
+
+impl Sandpile {
+    pub fn new(toppling: Toppling) -> Sandpile {
+        Sandpile { toppling }
+    }
+
+    pub fn toppling(&self) -> Toppling {
+        self.toppling
+    }
+
+    /// A pile of `grains` on the centre cell of an empty grid.
+    pub fn single_pile(size: (u16, u16), grains: u32) -> Vec<SandCell> {
+        let mut data = vec![SandCell::default(); size.0 as usize * size.1 as usize];
+        let center = size.1 as usize / 2 * size.0 as usize + size.0 as usize / 2;
+        data[center].grains = grains;
+        data
+    }
+
+    /// Drops a grain on `at`, wrapped around the edges, and relaxes the pile,
+    /// returning the avalanche it caused.
+    pub fn add_grain(grid: &mut Grid<SandCell>, at: IndexType) -> Avalanche {
+        let width = grid.get_size().width as usize;
+        let index = drop_grain(grid, at);
+        topple(grid.get_raw_mut_data(), width, vec![index], None)
+    }
+
+    /// Relaxes the whole pile, for example after starting from random
+    /// heights or a single large pile.
+    pub fn stabilize(grid: &mut Grid<SandCell>) -> Avalanche {
+        let width = grid.get_size().width as usize;
+        let data = grid.get_raw_mut_data();
+        let unstable = unstable_cells(data);
+        topple(data, width, unstable, None)
+    }
+}
+
+/// Adds a grain on `at`, wrapped around the edges, without toppling and
+/// returns the index of the cell.
+fn drop_grain(grid: &mut Grid<SandCell>, at: IndexType) -> usize {
+    let size = grid.get_size();
+    let index = at.1.rem_euclid(size.height as i32) as usize * size.width as usize
+        + at.0.rem_euclid(size.width as i32) as usize;
+    grid.get_raw_mut_data()[index].grains += 1;
+    index
+}
+
+fn unstable_cells(data: &[SandCell]) -> Vec<usize> {
+    (0..data.len()).filter(|&i| !data[i].is_stable()).collect()
+}
+
+/// Synchronous toppling starting from the `unstable` cells, for at most
+/// `max_sweeps` sweeps. Only cells next to a toppling are looked at again,
+/// so small avalanches on a large grid stay cheap.
+fn topple(
+    data: &mut [SandCell],
+    width: usize,
+    mut unstable: Vec<usize>,
+    max_sweeps: Option<usize>,
+) -> Avalanche {
+    let height = data.len() / width;
+    let mut toppled = vec![false; data.len()];
+    let mut queued = vec![false; data.len()];
+    let mut avalanche = Avalanche::default();
+    unstable.retain(|&i| !data[i].is_stable());
+    while !unstable.is_empty() && max_sweeps.is_none_or(|m| avalanche.duration < m) {
+        avalanche.duration += 1;
+        let topplings: Vec<(usize, u32)> = unstable
+            .iter()
+            .map(|&i| (i, data[i].grains / THRESHOLD))
+            .collect();
+        let mut next = Vec::new();
+        for &(i, n) in &topplings {
+            data[i].grains -= n * THRESHOLD;
+            avalanche.size += n as usize;
+            if !toppled[i] {
+                toppled[i] = true;
+                avalanche.area += 1;
+            }
+        }
+        for &(i, n) in &topplings {
+            let (x, y) = (i % width, i / width);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width),
+                (y + 1 < height).then(|| i + width),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                data[j].grains += n;
+                if !data[j].is_stable() && !queued[j] {
+                    queued[j] = true;
+                    next.push(j);
+                }
+            }
+        }
+        for &(i, _) in &topplings {
+            if !data[i].is_stable() && !queued[i] {
+                queued[i] = true;
+                next.push(i);
+            }
+        }
+        for &i in &next {
+            queued[i] = false;
+        }
+        unstable = next;
+    }
+    avalanche
+}
+
+impl RuleSet for Sandpile {
+    type Data = SandCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    /// One synchronous sweep for a single cell. Unlike `next_generation`
+    /// the window wraps around, so the edges are not sinks here.
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let me = source[4].grains;
+        let received: u32 = [1, 3, 5, 7]
+            .iter()
+            .map(|&i| source[i].grains / THRESHOLD)
+            .sum();
+        SandCell::from(me % THRESHOLD + received)
+    }
+
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        let width = grid.get_size().width as usize;
+        let mut data = grid.get_raw_data().to_vec();
+        let unstable = unstable_cells(&data);
+        let max_sweeps = match self.toppling {
+            Toppling::Sweep => Some(1),
+            Toppling::Relax => None,
+        };
+        topple(&mut data, width, unstable, max_sweeps);
+        Some(data)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        (0..THRESHOLD).map(SandCell::from).collect()
+    }
+
+    fn supports_cell_updates(&self) -> bool {
+        false
+    }
+}
+
+/// Adds grains at a fixed cell or at random and records the avalanches in
+/// shared statistics. With `Toppling::Relax` it drops one grain per
+/// generation and relaxes the pile itself; with `Toppling::Sweep` it drops a
+/// grain whenever the pile is stable and follows the avalanche sweep by
+/// sweep as `Sandpile` topples it.
+pub struct GrainDropper {
+    target: Target,
+    toppling: Toppling,
+    last: IndexType,
+    toppled: HashSet<usize>,
+    stats: Arc<Mutex<AvalancheStats>>,
+}
+
+enum Target {
+    At(IndexType),
+    Random(CellRng),
+}
+
+impl GrainDropper {
+    /// `toppling` should match the `Sandpile` the dropper runs with.
+    pub fn at(position: (u16, u16), toppling: Toppling) -> GrainDropper {
+        let at = (position.0 as i32, position.1 as i32);
+        GrainDropper::new(Target::At(at), toppling, at)
+    }
+
+    /// Drops grains on cells picked by a generator seeded with `seed`.
+    pub fn random(seed: u64, toppling: Toppling) -> GrainDropper {
+        let rng = CellRng::for_generation(seed, 0);
+        GrainDropper::new(Target::Random(rng), toppling, (0, 0))
+    }
+
+    fn new(target: Target, toppling: Toppling, last: IndexType) -> GrainDropper {
+        GrainDropper {
+            target,
+            toppling,
+            last,
+            toppled: HashSet::new(),
+            stats: Arc::new(Mutex::new(AvalancheStats::default())),
+        }
+    }
+
+    /// A handle to the statistics that stays valid after the dropper has
+    /// been moved into a `Game`.
+    pub fn stats(&self) -> Arc<Mutex<AvalancheStats>> {
+        Arc::clone(&self.stats)
+    }
+}
+
+impl GrainDropper {
+    fn next_target(&mut self, grid: &Grid<SandCell>) -> IndexType {
+        let size = grid.get_size();
+        match &mut self.target {
+            Target::At(at) => *at,
+            Target::Random(rng) => (
+                rng.gen_range(0..size.width as i32),
+                rng.gen_range(0..size.height as i32),
+            ),
+        }
+    }
+
+    /// Runs after each sweep: the cells still unstable are the ones the next
+    /// sweep topples, so they are counted now. Once nothing is unstable the
+    /// avalanche is over and the next grain drops.
+    fn follow_sweep(&mut self, grid: &mut Grid<SandCell>) {
+        let mut unstable = unstable_cells(grid.get_raw_data());
+        let shared = Arc::clone(&self.stats);
+        let mut stats = shared.lock().unwrap();
+        if unstable.is_empty() {
+            if let Some(avalanche) = stats.current.take() {
+                stats.record(avalanche);
+            }
+            self.toppled.clear();
+            self.last = self.next_target(grid);
+            let index = drop_grain(grid, self.last);
+            if !grid.get_raw_data()[index].is_stable() {
+                unstable.push(index);
+            }
+        }
+        let data = grid.get_raw_data();
+        let current = stats.current.get_or_insert_with(Avalanche::default);
+        if !unstable.is_empty() {
+            current.duration += 1;
+        }
+        for i in unstable {
+            current.size += (data[i].grains / THRESHOLD) as usize;
+            if self.toppled.insert(i) {
+                current.area += 1;
+            }
+        }
+    }
+}
+
+impl Agent<SandCell> for GrainDropper {
+    fn step(&mut self, grid: &mut Grid<SandCell>) {
+        match self.toppling {
+            Toppling::Relax => {
+                self.last = self.next_target(grid);
+                let avalanche = Sandpile::add_grain(grid, self.last);
+                self.stats.lock().unwrap().record(avalanche);
+            }
+            Toppling::Sweep => self.follow_sweep(grid),
+        }
+    }
+
+    fn position(&self) -> IndexType {
+        self.last
+    }
+
+    fn get_color(&self) -> Color {
+        (0, 255, 0, 255)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grains(grid: &Grid<SandCell>) -> Vec<u32> {
+        grid.get_raw_data().iter().map(|c| c.grains()).collect()
+    }
+
+    fn pile(grains: &[u32], width: u16) -> Grid<SandCell> {
+        let data = grains.iter().map(|&g| SandCell::from(g)).collect();
+        Grid::init_with_data(data, width).unwrap()
+    }
+
+    fn stabilized(grains: &[u32], width: u16) -> Vec<u32> {
+        let mut grid = pile(grains, width);
+        Sandpile::stabilize(&mut grid);
+        self::grains(&grid)
+    }
+
+    #[test]
+    fn four_grains_topple_once() {
+        let mut grid = Grid::init_with_data(Sandpile::single_pile((3, 3), 4), 3).unwrap();
+        let avalanche = Sandpile::stabilize(&mut grid);
+        assert_eq!(grains(&grid), vec![0, 1, 0, 1, 0, 1, 0, 1, 0]);
+        let expected = Avalanche {
+            size: 1,
+            duration: 1,
+            area: 1,
+        };
+        assert_eq!(avalanche, expected);
+    }
+
+    #[test]
+    fn identity_leaves_recurrent_piles_alone() {
+        // the identity of the sandpile group is (6 - (6)°)°
+        let sixes = vec![6; 16];
+        let relaxed = stabilized(&sixes, 4);
+        let difference: Vec<u32> = relaxed.iter().map(|g| 6 - g).collect();
+        let identity = stabilized(&difference, 4);
+        let doubled: Vec<u32> = identity.iter().map(|g| g * 2).collect();
+        assert_eq!(stabilized(&doubled, 4), identity);
+        let full: Vec<u32> = identity.iter().map(|g| g + 3).collect();
+        assert_eq!(stabilized(&full, 4), vec![3; 16]);
+    }
+
+    #[test]
+    fn avalanche_counts_topplings_sweeps_and_cells() {
+        let mut grid = pile(&[3, 3, 3], 3);
+        let avalanche = Sandpile::add_grain(&mut grid, (1, 0));
+        assert_eq!(grains(&grid), vec![0, 2, 0]);
+        let expected = Avalanche {
+            size: 3,
+            duration: 2,
+            area: 3,
+        };
+        assert_eq!(avalanche, expected);
+
+        let mut stats = AvalancheStats::default();
+        stats.record(avalanche);
+        stats.record(Avalanche::default());
+        stats.record(avalanche);
+        assert_eq!(stats.grains(), 3);
+        assert_eq!(stats.mean_size(), 2.0);
+        assert_eq!(stats.max_size(), 3);
+        assert_eq!(stats.max_duration(), 2);
+        assert_eq!(stats.size_distribution(), vec![(3, 2)]);
+    }
+
+    #[test]
+    fn sweeping_dropper_records_the_same_avalanches() {
+        let run = |toppling| {
+            let rules = Sandpile::new(toppling);
+            let mut dropper = GrainDropper::at((2, 2), toppling);
+            let stats = dropper.stats();
+            let mut grid = pile(&[0; 25], 5);
+            let mut generation = 0;
+            while stats.lock().unwrap().grains() < 60 {
+                let data = rules.next_generation(&grid, generation).unwrap();
+                grid = Grid::init_with_data(data, 5).unwrap();
+                dropper.step(&mut grid);
+                generation += 1;
+            }
+            let avalanches = stats.lock().unwrap().avalanches()[..60].to_vec();
+            avalanches
+        };
+        let swept = run(Toppling::Sweep);
+        assert!(swept.iter().any(|a| a.duration > 1));
+        assert_eq!(swept, run(Toppling::Relax));
+    }
+}
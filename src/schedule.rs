@@ -0,0 +1,135 @@
+use crate::{GError, GResult, RuleSet};
+
+/// Order in which `Game::next_step` updates cells. Every schedule except
+/// `Synchronous` and `Asynchronous` updates cells in place, so a cell sees
+/// the neighbours already updated before it in the same generation; those
+/// schedules go through `RuleSet::next_with_rng` even for rules that
+/// provide `next_generation`. All but `Synchronous` pick cells one by one
+/// and so only work with rules whose `RuleSet::supports_cell_updates` is
+/// true. Random choices come from the game seed.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Schedule {
+    /// Every cell at once from the previous generation.
+    #[default]
+    Synchronous,
+    /// As many single-cell updates as there are cells, each on a cell
+    /// picked at random, so some cells are updated several times and others
+    /// not at all.
+    RandomSequential,
+    /// Every cell at once, but each one only takes its new state with the
+    /// given probability and otherwise keeps the old one.
+    Asynchronous(f64),
+    /// The cells with `x + y` even at once, then the odd ones.
+    Checkerboard,
+    /// One cell at a time, row by row from the top left.
+    Ordered,
+}
+
+impl Schedule {
+    /// Checks the schedule's parameters and that `rules` can be run with it.
+    pub fn check<R: RuleSet>(&self, rules: &R) -> GResult<()> {
+        match self {
+            Schedule::Asynchronous(p) if !(0.0..=1.0).contains(p) => Err(GError::ScheduleError(
+                format!("update probability {} must be between 0 and 1", p),
+            )),
+            Schedule::Synchronous => Ok(()),
+            _ if !rules.supports_cell_updates() => Err(GError::ScheduleError(format!(
+                "{:?} needs rules that update one cell at a time",
+                self
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ForestCell, ForestFire, Game, Grid, IsotropicRule, LifeCell, Margolus, SandCell, Sandpile,
+        TurmiteWorld,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn cells<R: RuleSet>(game: &Game<R>) -> Vec<R::Data> {
+        let size = game.get_view_size();
+        (0..size.height as i32)
+            .flat_map(|y| (0..size.width as i32).map(move |x| (x, y)))
+            .map(|c| game.get_view_cell(c).clone())
+            .collect()
+    }
+
+    #[test]
+    fn synchronous_updates_every_cell_from_the_last_generation() {
+        let rules = IsotropicRule::parse("B3/S23").unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let data: Vec<LifeCell> = (0..16 * 12)
+            .map(|_| LifeCell::from(rng.gen::<bool>()))
+            .collect();
+        let mut grid = Grid::init_with_data(data.clone(), 16).unwrap();
+        let mut game = Game::init_with_rules(rules.clone(), data, 16).unwrap();
+        let alive = |cells: &[LifeCell]| cells.iter().map(LifeCell::is_alive).collect::<Vec<_>>();
+        for _ in 0..4 {
+            let next: Vec<LifeCell> = (0..12)
+                .flat_map(|y| (0..16).map(move |x| (x, y)))
+                .map(|c| rules.next(&grid.get_area(c, rules.source_size())))
+                .collect();
+            grid = Grid::init_with_data(next, 16).unwrap();
+            game.next_step();
+            assert_eq!(alive(&cells(&game)), alive(grid.get_raw_data()));
+        }
+    }
+
+    #[test]
+    fn seeded_schedules_are_deterministic() {
+        let run = |schedule| {
+            let rules = ForestFire::new(0.05, 0.01).unwrap();
+            let mut game = Game::init_generated_data(rules, (20, 20), 11, |_| ForestCell::Empty)
+                .unwrap()
+                .with_schedule(schedule)
+                .unwrap();
+            for _ in 0..30 {
+                game.next_step();
+            }
+            cells(&game)
+        };
+        for schedule in [
+            Schedule::Checkerboard,
+            Schedule::Ordered,
+            Schedule::RandomSequential,
+            Schedule::Asynchronous(0.5),
+        ] {
+            let first = run(schedule);
+            assert!(
+                first.iter().any(|c| *c != ForestCell::Empty),
+                "{:?}",
+                schedule
+            );
+            assert_eq!(first, run(schedule), "{:?}", schedule);
+        }
+    }
+
+    #[test]
+    fn check_rejects_rules_that_cannot_update_single_cells() {
+        let life = IsotropicRule::parse("B3/S23").unwrap();
+        let margolus = Margolus::preset("tron").unwrap();
+        let (sandpile, turmites) = (Sandpile::default(), TurmiteWorld::default());
+        let in_place = [
+            Schedule::Checkerboard,
+            Schedule::Ordered,
+            Schedule::RandomSequential,
+            Schedule::Asynchronous(0.5),
+        ];
+        for schedule in in_place {
+            assert!(schedule.check(&life).is_ok());
+            assert!(schedule.check(&margolus).is_err(), "{:?}", schedule);
+            assert!(schedule.check(&sandpile).is_err(), "{:?}", schedule);
+            assert!(schedule.check(&turmites).is_err(), "{:?}", schedule);
+        }
+        assert!(Schedule::Synchronous.check(&margolus).is_ok());
+        assert!(Schedule::Asynchronous(1.5).check(&life).is_err());
+        let data = vec![SandCell::default(); 16];
+        let game = Game::init_with_rules(Sandpile::default(), data, 4).unwrap();
+        assert!(game.with_schedule(Schedule::Asynchronous(0.5)).is_err());
+    }
+}
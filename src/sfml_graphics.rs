@@ -1,59 +1,100 @@
-use crate::{ColoredDataType, Game, RuleSet};
+use crate::{Action, ColoredDataType, Controller, Game, KeyPress, RuleSet, Viewport};
 use sfml::graphics::{Color, Font, RenderTarget, RenderWindow, Sprite, Text, Texture, View};
 use sfml::system::Vector2f;
-use sfml::window::{ContextSettings, Event, Key, Style};
+use sfml::window::{mouse, ContextSettings, Event, Style};
 use std::time::Instant;
 use thiserror::Error;
 
+const TITLE: &str = "GOL";
+
 pub fn run<R>(window_size: (u32, u32), game: &mut Game<R>) -> Result<(), SfmlError>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let size = game.get_size();
-    let area: Vector2f = (size.width as f32, size.height as f32).into();
+    let (_, size) = game.to_image();
+    let mut viewport = Viewport::new(size, (window_size.0 as f32, window_size.1 as f32));
 
     let font = Font::from_file("sansation.ttf").ok_or("Font-file not found")?;
     let mut fps_text = Text::default();
     fps_text.set_font(&font);
     let ctx_settings = ContextSettings::default();
-    let mut window = RenderWindow::new(window_size, "GOL", Style::CLOSE, &ctx_settings);
-    window.set_framerate_limit(60);
+    let mut controller = Controller::new(game.get_bindings().clone());
+    let title = controller.title(TITLE, game);
+    let mut window = RenderWindow::new(window_size, title.as_str(), Style::CLOSE, &ctx_settings);
     let mut texture = Texture::new().ok_or("New texture can not be created")?;
     if !texture.create(size.width as u32, size.height as u32) {
         return Err("Texture can not be created".into());
     };
-    let view = View::new(area * 0.5, area);
-    window.set_view(&view);
-    let mut is_playing = false;
-    let mut show_fps = false;
+    // The grid is drawn through a view that follows the viewport, and the
+    // fps counter through the default view so it does not zoom.
+    let text_view = window.default_view().to_owned();
+    let mut view = View::new(Vector2f::default(), Vector2f::default());
+    let mut drag_from = None;
     let mut prev_time = Instant::now();
     loop {
         while let Some(event) = window.poll_event() {
             match event {
                 Event::Closed => return Ok(()),
-                Event::KeyPressed {
-                    code: Key::Space, ..
-                } => is_playing ^= true,
-                Event::KeyPressed { code: Key::F, .. } => show_fps ^= true,
+                Event::KeyPressed { code, shift, .. } => {
+                    let press = KeyPress::from_code(&code, shift);
+                    match press.and_then(|p| controller.press(p, game, &mut viewport)) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(_) => window.set_title(&controller.title(TITLE, game)),
+                        None => (),
+                    }
+                }
+                // The wheel zooms around the pointer and the middle button
+                // drags the view.
+                Event::MouseWheelScrolled { delta, x, y, .. } => {
+                    let anchor = (x as f32, y as f32);
+                    if delta > 0.0 {
+                        viewport.zoom_in(anchor);
+                    } else if delta < 0.0 {
+                        viewport.zoom_out(anchor);
+                    }
+                }
+                Event::MouseMoved { x, y } if mouse::Button::Middle.is_pressed() => {
+                    if let Some((from_x, from_y)) = drag_from.replace((x, y)) {
+                        viewport.pan((x - from_x) as f32, (y - from_y) as f32);
+                    }
+                }
+                Event::MouseMoved { .. } => drag_from = None,
                 _ => (),
             }
         }
-        if is_playing {
-            game.next_step();
+        // Left paints with the brush and right erases, mapped through the
+        // viewport to the image the grid is drawn in.
+        let painting = mouse::Button::Left.is_pressed();
+        if window.has_focus() && (painting || mouse::Button::Right.is_pressed()) {
+            let pointer = window.mouse_position();
+            let (x, y) = viewport.to_image((pointer.x as f32, pointer.y as f32));
+            match game.pick(x, y) {
+                Some(cell) if painting => game.paint(cell),
+                Some(cell) => game.erase(cell),
+                None => {}
+            }
         }
+        controller.wait_for_frame();
+        controller.update(game);
         let curr_time = Instant::now();
         let fps = 1.0 / (curr_time - prev_time).as_secs_f32();
         prev_time = curr_time;
-        let (data, size) = game.to_raw_colors();
+        let (data, size) = game.to_image();
         unsafe {
             texture.update_from_pixels(&*data, size.width as u32, size.height as u32, 0, 0);
         }
         let sprite = Sprite::with_texture(&texture);
+        let (x, y) = viewport.get_corner();
+        let (width, height) = viewport.get_visible_size();
+        view.set_size((width, height));
+        view.set_center((x + width / 2.0, y + height / 2.0));
 
         window.clear(Color::BLACK);
+        window.set_view(&view);
         window.draw(&sprite);
-        if show_fps {
+        window.set_view(&text_view);
+        if controller.show_fps() {
             fps_text.set_string(&fps.to_string());
             window.draw(&fps_text);
         }
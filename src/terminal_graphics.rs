@@ -1,17 +1,37 @@
 use std::io;
 use std::io::{stdout, Stdout, StdoutLock, Write};
-use std::time::Duration;
 
 use crossterm::cursor::{Hide, MoveTo, MoveToNextLine, Show};
-use crossterm::event::{poll, read, Event, KeyCode};
-use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::event::{
+    poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, size as terminal_size, size, DisableLineWrap,
-    EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, SetSize,
+    disable_raw_mode, enable_raw_mode, size as terminal_size, size, Clear, ClearType,
+    DisableLineWrap, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, SetSize,
 };
 use crossterm::QueueableCommand;
 
-use crate::{Color as DataColor, ColoredDataType, Game, RuleSet};
+use crate::{
+    Action, Color as DataColor, ColoredDataType, Controller, Game, IndexType, Key, KeyPress,
+    Pattern, RuleSet, TextLayout, Viewport,
+};
+
+/// Terminals redraw slower than windows, so frames come less often.
+const FPS: u32 = 30;
+
+/// Drawn over the cell under the edit cursor.
+const CURSOR_COLOR: DataColor = (255, 0, 255, 255);
+
+/// Characters for cells from dark to bright, when cells have no character
+/// of their own.
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+/// Gives the character a cell is printed as.
+type CellChar<D> = fn(&D) -> char;
 
 struct Ctx {
     orig_size: (u16, u16),
@@ -31,6 +51,7 @@ impl Ctx {
         };
         s.get_buffer()
             .queue(EnterAlternateScreen)?
+            .queue(EnableMouseCapture)?
             .queue(DisableLineWrap)?
             .queue(SetSize(size.0, size.1))?
             .queue(Hide)?
@@ -40,6 +61,7 @@ impl Ctx {
     fn close(&mut self) -> Result<(), io::Error> {
         let size = self.orig_size;
         self.get_buffer()
+            .queue(DisableMouseCapture)?
             .queue(LeaveAlternateScreen)?
             .queue(SetSize(size.0, size.1))?
             .queue(Show)?
@@ -62,69 +84,653 @@ impl Drop for Ctx {
     }
 }
 
-pub fn run<R>(window_size: (u16, u16), game: &mut Game<R>) -> Result<(), io::Error>
+/// Draws the game until quit. With `chars`, cells are printed as characters
+/// in their colour instead of blocks of colour, which is also how terminals
+/// without colours show them.
+pub fn run<R>(
+    window_size: (u16, u16),
+    game: &mut Game<R>,
+    chars: Option<CellChar<R::Data>>,
+) -> Result<(), io::Error>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
     let mut ctx = Ctx::open(window_size, stdout())?;
-    let mut is_playing = false;
-    draw(game, ctx.get_buffer())?;
+    let mut controller = Controller::new(game.get_bindings().clone()).with_fps(FPS);
+    let mut editor = Editor::default();
+    let text = chars.is_some() || ColorDepth::detect() == ColorDepth::Mono;
+    let mut view = View::new(game, text)?;
+    draw(
+        game,
+        &view,
+        &controller,
+        Some(&editor),
+        chars,
+        ctx.get_buffer(),
+    )?;
+    // Events are handled as they come and drawn at the next frame.
+    let mut redraw = false;
     loop {
-        if poll(Duration::from_millis(50))? {
-            match read()? {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char(' ') => is_playing ^= true,
-                    KeyCode::Right if !is_playing => {
-                        game.next_step();
-                        draw(game, ctx.get_buffer())?
+        if poll(controller.time_to_frame())? {
+            let changed = match read()? {
+                Event::Key(key) if editor.prompt.is_some() => {
+                    editor.type_key(key.code, game);
+                    true
+                }
+                Event::Key(key) => {
+                    let press = key_press(key);
+                    match press.and_then(|p| controller.press(p, game, &mut view.viewport)) {
+                        Some(Action::Quit) => break,
+                        // Only whole zoom levels draw evenly in characters.
+                        Some(Action::FitPattern | Action::FitAll) => {
+                            view.viewport.snap_zoom();
+                            true
+                        }
+                        Some(_) => true,
+                        None => editor.edit_key(key.code, game),
                     }
-                    KeyCode::Char('c') | KeyCode::Esc => break,
-                    _ => {}
-                },
-                Event::Resize(_, _) => draw(game, ctx.get_buffer())?,
-                _ => {}
+                }
+                Event::Mouse(mouse) => editor.click(mouse, game, &view) || view.mouse(mouse),
+                Event::Resize(_, _) => {
+                    view.resize(game)?;
+                    true
+                }
+                _ => false,
+            };
+            redraw |= changed;
+        }
+
+        if controller.frame_due() {
+            let is_playing = controller.is_playing();
+            controller.update(game);
+            if redraw || is_playing {
+                // The cursor only shows while paused.
+                let editor = (!is_playing).then_some(&editor);
+                draw(game, &view, &controller, editor, chars, ctx.get_buffer())?;
+                redraw = false;
             }
         }
+    }
 
-        if is_playing {
-            game.next_step();
-            draw(game, ctx.get_buffer())?;
+    Ok(())
+}
+
+/// Editing state kept between frames: the cursor, in cells of the slice
+/// shown, and the path of a pattern being typed after `p`.
+#[derive(Default)]
+struct Editor {
+    cursor: IndexType,
+    prompt: Option<String>,
+    message: String,
+}
+
+impl Editor {
+    /// WASD moves the cursor, Enter toggles the cell under it and `p` asks
+    /// for a pattern file to stamp at the cursor. Returns whether the key
+    /// was one of those.
+    fn edit_key<R>(&mut self, code: KeyCode, game: &mut Game<R>) -> bool
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let (x, y) = self.cursor;
+        let moved = match code {
+            KeyCode::Char('w') => (x, y - 1),
+            KeyCode::Char('a') => (x - 1, y),
+            KeyCode::Char('s') => (x, y + 1),
+            KeyCode::Char('d') => (x + 1, y),
+            KeyCode::Enter => {
+                game.toggle(game.view_index(self.cursor));
+                return true;
+            }
+            KeyCode::Char('p') => {
+                self.prompt = Some(String::new());
+                return true;
+            }
+            _ => return false,
+        };
+        let size = game.get_view_size();
+        self.cursor = (
+            moved.0.rem_euclid(size.width as i32),
+            moved.1.rem_euclid(size.height as i32),
+        );
+        true
+    }
+
+    /// Edits the path after `p` until Enter stamps the pattern or Esc gives
+    /// up.
+    fn type_key<R>(&mut self, code: KeyCode, game: &mut Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let Some(path) = self.prompt.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => path.push(c),
+            KeyCode::Backspace => {
+                path.pop();
+            }
+            KeyCode::Enter => {
+                let path = self.prompt.take().unwrap_or_default();
+                let stamped = Pattern::from_file(&path).and_then(|p| game.stamp(&p, self.cursor));
+                self.message = match stamped {
+                    Ok(()) => format!("stamped {}", path),
+                    Err(e) => e.to_string(),
+                };
+            }
+            KeyCode::Esc => self.prompt = None,
+            _ => {}
         }
     }
 
+    /// Left button paints and right erases every cell under the pointer,
+    /// which is several cells in the denser modes, and moves the cursor
+    /// there.
+    /// Returns whether anything changed.
+    fn click<R>(&mut self, mouse: MouseEvent, game: &mut Game<R>, view: &View) -> bool
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let paint = match mouse.kind {
+            MouseEventKind::Down(button) | MouseEventKind::Drag(button) => match button {
+                MouseButton::Left => true,
+                MouseButton::Right => false,
+                MouseButton::Middle => return false,
+            },
+            _ => return false,
+        };
+        let cells = view.cells_at(mouse.column, mouse.row);
+        for &cell in cells.iter() {
+            let index = game.view_index(cell);
+            if paint {
+                game.paint(index);
+            } else {
+                game.erase(index);
+            }
+        }
+        if let Some(&cell) = cells.first() {
+            self.cursor = cell;
+        }
+        !cells.is_empty()
+    }
+}
+
+/// How square grids are packed into characters. Quadrant and braille
+/// characters take a single colour, so they are only used for rules with
+/// two states. `Text` prints a character per cell, in any layout.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Mode {
+    Text,
+    Single,
+    HalfBlock,
+    Quadrant,
+    Braille,
+}
+
+/// Quadrant characters by their lit dots, top-left 1, top-right 2,
+/// bottom-left 4 and bottom-right 8.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Bits of the braille dots, row by row.
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+impl Mode {
+    /// Columns and rows of cells in a character.
+    fn dots(self) -> (u16, u16) {
+        match self {
+            Mode::Text | Mode::Single => (1, 1),
+            Mode::HalfBlock => (1, 2),
+            Mode::Quadrant => (2, 2),
+            Mode::Braille => (2, 4),
+        }
+    }
+
+    /// The quadrant or braille character with the dots in `lit` showing,
+    /// with dots numbered row by row.
+    fn character(self, lit: u8) -> char {
+        match self {
+            Mode::Braille => {
+                let bits = (0..8)
+                    .filter(|i| lit >> i & 1 == 1)
+                    .map(|i| BRAILLE_DOTS[i])
+                    .sum::<u32>();
+                char::from_u32(0x2800 + bits).unwrap_or(' ')
+            }
+            _ => QUADRANTS[lit as usize & 15],
+        }
+    }
+}
+
+/// The part of the slice the terminal shows. The viewport's image is the
+/// slice with one pixel per cell and its window is the terminal measured in
+/// cells: as many per character as the mode packs in, and two columns per
+/// cell in the hexagon layout. Only square grids zoom, as the other layouts
+/// need whole cells.
+struct View {
+    viewport: Viewport,
+    layout: TextLayout,
+    mode: Mode,
+    depth: ColorDepth,
+    /// Last position of the pointer while the middle button drags the view.
+    drag: Option<(u16, u16)>,
+}
+
+impl View {
+    fn new<R>(game: &Game<R>, text: bool) -> Result<View, io::Error>
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let layout = game.get_topology().text_layout();
+        let mode = if text { Mode::Text } else { Mode::Single };
+        let (mode, window) = View::window(layout, mode, game)?;
+        let mut viewport = Viewport::new(game.get_view_size(), window);
+        if layout != TextLayout::Square {
+            viewport = viewport.with_zoom_range(1.0, 1.0);
+        }
+        viewport.snap_zoom();
+        Ok(View {
+            viewport,
+            layout,
+            mode,
+            depth: ColorDepth::detect(),
+            drag: None,
+        })
+    }
+
+    /// The mode square grids are drawn in, which is the first that fits the
+    /// slice in the terminal or else the densest, and the window in cells.
+    /// The text mode is kept as it is. The last line is kept for the status
+    /// line.
+    fn window<R>(
+        layout: TextLayout,
+        mode: Mode,
+        game: &Game<R>,
+    ) -> Result<(Mode, (f32, f32)), io::Error>
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let (w, h) = size()?;
+        let (w, h) = (w as f32, h.saturating_sub(1) as f32);
+        let mode = match layout {
+            _ if mode == Mode::Text => Mode::Text,
+            TextLayout::Square => {
+                let modes: &[Mode] = if game.get_palette_colors().len() == 2 {
+                    &[Mode::Single, Mode::HalfBlock, Mode::Quadrant, Mode::Braille]
+                } else {
+                    &[Mode::Single, Mode::HalfBlock]
+                };
+                let size = game.get_view_size();
+                let fits = |mode: &&Mode| {
+                    let (x, y) = mode.dots();
+                    size.width as f32 <= w * x as f32 && size.height as f32 <= h * y as f32
+                };
+                *modes.iter().find(fits).unwrap_or(&modes[modes.len() - 1])
+            }
+            _ => Mode::Single,
+        };
+        let (x, y) = mode.dots();
+        let window = match layout {
+            // One column is left for the stagger.
+            TextLayout::Staggered => (((w - 1.0) / 2.0).floor(), h),
+            _ => (w * x as f32, h * y as f32),
+        };
+        Ok((mode, window))
+    }
+
+    /// Keeps the mode and the zoom if the mode still fits, and otherwise
+    /// shows the whole slice in the new one.
+    fn resize<R>(&mut self, game: &Game<R>) -> Result<(), io::Error>
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let (mode, window) = View::window(self.layout, self.mode, game)?;
+        self.viewport.set_window(window);
+        if mode != self.mode {
+            self.mode = mode;
+            self.viewport.fit_all();
+            self.viewport.snap_zoom();
+        }
+        Ok(())
+    }
+
+    /// Lines of the terminal the cells take.
+    fn lines(&self) -> u16 {
+        let (_, height) = self.viewport.get_window();
+        (height / self.mode.dots().1 as f32) as u16
+    }
+
+    /// The cell at column `x` and row `y` of the window, if any.
+    fn cell(&self, x: i32, y: i32) -> Option<IndexType> {
+        self.viewport.pixel_at((x as f32 + 0.5, y as f32 + 0.5))
+    }
+
+    /// Whether the cells on row `y` of the window are pushed one column to
+    /// the right in the hexagon layout, which is so on even rows of cells.
+    fn is_indented(&self, y: i32) -> bool {
+        let (_, row) = self.viewport.to_image((0.0, y as f32 + 0.5));
+        (row.floor() as i32).rem_euclid(2) == 0
+    }
+
+    /// Column and row of the window where `cell` is drawn, if it is in the
+    /// window.
+    fn position(&self, cell: IndexType) -> Option<(i32, i32)> {
+        let (x, y) = self
+            .viewport
+            .to_window((cell.0 as f32 + 0.5, cell.1 as f32 + 0.5));
+        let (width, height) = self.viewport.get_window();
+        let inside = x >= 0.0 && y >= 0.0 && x < width && y < height;
+        inside.then_some((x.floor() as i32, y.floor() as i32))
+    }
+
+    /// Cells of the slice shown under the character at `column` and `row`.
+    fn cells_at(&self, column: u16, row: u16) -> Vec<IndexType> {
+        let (column, row) = (column as i32, row as i32);
+        let cells = match self.layout {
+            TextLayout::Staggered => {
+                let indent = self.is_indented(row) as i32;
+                vec![self.cell((column - indent).div_euclid(2), row)]
+            }
+            TextLayout::Square => {
+                let (x, y) = self.mode.dots();
+                let (x, y) = (x as i32, y as i32);
+                (0..x * y)
+                    .map(|i| self.cell(column * x + i % x, row * y + i / x))
+                    .collect()
+            }
+            _ => vec![self.cell(column, row)],
+        };
+        let mut cells: Vec<_> = cells.into_iter().flatten().collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    /// Colour of a cell as the terminal can show it.
+    fn color(&self, color: DataColor) -> Color {
+        self.depth.color(color)
+    }
+
+    /// Cells shown at column `x` and row `y` of the window. Zoomed out, a
+    /// character or dot covers several cells.
+    fn cells_under<R>(&self, game: &Game<R>, x: i32, y: i32) -> impl Iterator<Item = IndexType>
+    where
+        R: RuleSet,
+    {
+        let (columns, rows) = if self.viewport.get_zoom() >= 1.0 {
+            match self.cell(x, y) {
+                Some((x, y)) => (x..x + 1, y..y + 1),
+                None => (0..0, 0..0),
+            }
+        } else {
+            // The cells whose middles are on the character.
+            let (x0, y0) = self.viewport.to_image((x as f32, y as f32));
+            let (x1, y1) = self.viewport.to_image((x as f32 + 1.0, y as f32 + 1.0));
+            let span = |from: f32, to: f32, len: u16| {
+                (from - 0.5).ceil().max(0.0) as i32..(to - 0.5).ceil().min(len as f32) as i32
+            };
+            let size = game.get_view_size();
+            (span(x0, x1, size.width), span(y0, y1, size.height))
+        };
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+    }
+
+    /// Whether the dot at column `x` and row `y` of the window shows a cell
+    /// that does not look like `empty`. Zoomed out, a dot shows if any of the
+    /// cells it covers does, so that small patterns do not disappear.
+    fn is_lit<R>(&self, game: &Game<R>, empty: DataColor, x: i32, y: i32) -> bool
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        self.cells_under(game, x, y)
+            .any(|cell| game.get_view_color(cell) != empty)
+    }
+
+    /// The wheel zooms around the pointer and the middle button drags the
+    /// view. Returns whether the view moved.
+    fn mouse(&mut self, mouse: MouseEvent) -> bool {
+        let (column, row) = (mouse.column, mouse.row);
+        // Characters hold several cells in the denser modes and cells are
+        // two columns wide in the hexagon layout.
+        let scale = match self.layout {
+            TextLayout::Staggered => (0.5, 1.0),
+            _ => {
+                let (x, y) = self.mode.dots();
+                (x as f32, y as f32)
+            }
+        };
+        let anchor = (
+            (column as f32 + 0.5) * scale.0,
+            (row as f32 + 0.5) * scale.1,
+        );
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.viewport.zoom_in(anchor),
+            MouseEventKind::ScrollDown => self.viewport.zoom_out(anchor),
+            MouseEventKind::Down(MouseButton::Middle) => {
+                self.drag = Some((column, row));
+                return false;
+            }
+            MouseEventKind::Drag(MouseButton::Middle) => {
+                let Some((x, y)) = self.drag.replace((column, row)) else {
+                    return false;
+                };
+                let dx = (column as f32 - x as f32) * scale.0;
+                let dy = (row as f32 - y as f32) * scale.1;
+                self.viewport.pan(dx, dy);
+            }
+            MouseEventKind::Up(MouseButton::Middle) => {
+                self.drag = None;
+                return false;
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn draw<R, W: Write>(
+    game: &Game<R>,
+    view: &View,
+    controller: &Controller,
+    editor: Option<&Editor>,
+    chars: Option<CellChar<R::Data>>,
+    mut out: W,
+) -> Result<(), io::Error>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    match (view.layout, view.mode) {
+        (_, Mode::Text) => text_draw(game, view, chars, &mut out)?,
+        (TextLayout::Staggered, _) => hex_draw(game, view, &mut out)?,
+        (TextLayout::Triangles, _) => triangle_draw(game, view, &mut out)?,
+        (TextLayout::Square, Mode::Single) => simple_draw(game, view, &mut out)?,
+        (TextLayout::Square, Mode::HalfBlock) => double_draw(game, view, &mut out)?,
+        (TextLayout::Square, Mode::Quadrant | Mode::Braille) => dots_draw(game, view, &mut out)?,
+    }
+    if let Some(editor) = editor {
+        cursor_draw(game, view, editor.cursor, chars, &mut out)?;
+    }
+    let mut status = format!("seed {}", game.get_seed());
+    let names = game.get_layer_names();
+    if names.len() > 1 {
+        let layer = game.get_layer().map_or("all layers", |l| names[l]);
+        status += &format!("  {}", layer);
+    }
+    if view.viewport.get_zoom() != 1.0 {
+        status += &format!("  zoom {}", view.viewport.get_zoom());
+    }
+    status += &format!("  {}", controller.speed_text());
+    if let Some(editor) = editor {
+        let (x, y) = editor.cursor;
+        status += &format!("  brush {}  {},{}", game.get_brush(), x, y);
+        match &editor.prompt {
+            Some(path) => status += &format!("  pattern: {}_", path),
+            None => status += &format!("  {}", editor.message),
+        }
+    }
+    if !controller.get_message().is_empty() {
+        status += &format!("  {}", controller.get_message());
+    }
+    out.queue(MoveTo(0, view.lines()))?
+        .queue(ResetColor)?
+        .queue(Print(status))?
+        .queue(Clear(ClearType::UntilNewLine))?
+        .flush()?;
     Ok(())
 }
 
-fn draw<R, W: Write>(game: &Game<R>, out: W) -> Result<(), io::Error>
+/// The key press of a terminal key event. Shifted letters arrive in upper
+/// case.
+fn key_press(event: KeyEvent) -> Option<KeyPress> {
+    let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+    let key = match event.code {
+        KeyCode::Char(' ') => Key::Space,
+        KeyCode::Char(c) => Key::Char(c.to_ascii_lowercase()),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Escape,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::F(n) => Key::F(n),
+        _ => return None,
+    };
+    Some(KeyPress::new(key, shift))
+}
+
+/// Colour of the cell at column `x` and row `y` of the window, black off the
+/// grid. Zoomed out, the brightest of the cells covered is shown, so that
+/// small patterns do not disappear.
+fn view_color<R>(game: &Game<R>, view: &View, x: i32, y: i32) -> Color
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    view.cells_under(game, x, y)
+        .map(|cell| game.get_view_color(cell))
+        .max_by_key(|&color| brightness(color))
+        .map_or(Color::Black, |color| view.color(color))
+}
+
+/// The character `cell` of the slice shown is printed as in the text mode:
+/// its own, or else a shade for its brightness.
+fn cell_char<R>(game: &Game<R>, chars: Option<CellChar<R::Data>>, cell: IndexType) -> char
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    if let Some(chars) = chars {
+        return chars(game.get_view_cell(cell));
+    }
+    let brightness = brightness(game.get_view_color(cell));
+    let shade = brightness * (SHADES.len() as u32 - 1) / (1000 * 255 * 255);
+    SHADES[shade as usize] as char
+}
+
+/// Perceived brightness of `color` weighted by its opacity, up to
+/// `1000 * 255 * 255`.
+fn brightness((r, g, b, a): DataColor) -> u32 {
+    (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) * a as u32
+}
+
+/// Marks the cell under the edit cursor, if it is in view.
+fn cursor_draw<R, W: Write>(
+    game: &Game<R>,
+    view: &View,
+    cursor: IndexType,
+    chars: Option<CellChar<R::Data>>,
+    mut out: W,
+) -> Result<(), io::Error>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let (_, h) = size()?;
-    if h < game.get_size().height {
-        double_draw(game, out)
-    } else {
-        simple_draw(game, out)
+    let Some((x, y)) = view.position(cursor) else {
+        return Ok(());
+    };
+    // The cell's character in reverse, which needs no colours.
+    if view.mode == Mode::Text {
+        let column = match view.layout {
+            TextLayout::Staggered => x * 2 + view.is_indented(y) as i32,
+            _ => x,
+        };
+        return out
+            .queue(MoveTo(column as u16, y as u16))?
+            .queue(SetAttribute(Attribute::Reverse))?
+            .queue(Print(cell_char(game, chars, cursor)))?
+            .queue(SetAttribute(Attribute::Reset))?
+            .flush();
     }
+    let color = view.color(game.get_view_color(cursor));
+    let cursor_color = view.color(CURSOR_COLOR);
+    let (column, row, background, foreground, mark) = match (view.layout, view.mode) {
+        // Only the cursor's half of the character changes colour.
+        (TextLayout::Square, Mode::HalfBlock) => {
+            let other = view_color(game, view, x, y ^ 1);
+            let half = if y % 2 == 0 { "▀" } else { "▄" };
+            (x, y / 2, other, cursor_color, half.to_string())
+        }
+        // Only the cursor's dot shows.
+        (TextLayout::Square, Mode::Quadrant | Mode::Braille) => {
+            let (dx, dy) = view.mode.dots();
+            let (dx, dy) = (dx as i32, dy as i32);
+            let dot = (y % dy * dx + x % dx) as u8;
+            let background = view.color(game.get_palette_colors()[0]);
+            let mark = view.mode.character(1 << dot).to_string();
+            (x / dx, y / dy, background, cursor_color, mark)
+        }
+        (TextLayout::Square, _) => (x, y, color, cursor_color, "+".to_string()),
+        (TextLayout::Staggered, _) => {
+            let indent = view.is_indented(y) as i32;
+            (x * 2 + indent, y, color, cursor_color, "[]".to_string())
+        }
+        // Triangles are drawn in the foreground, so the cursor goes behind.
+        (TextLayout::Triangles, _) => {
+            let up = (cursor.0 + cursor.1) % 2 == 0;
+            let mark = if up { "▲" } else { "▼" };
+            (x, y, cursor_color, color, mark.to_string())
+        }
+    };
+    out.queue(MoveTo(column as u16, row as u16))?
+        .queue(SetBackgroundColor(background))?
+        .queue(SetForegroundColor(foreground))?
+        .queue(Print(mark))?
+        .flush()
 }
 
-fn double_draw<R, W: Write>(game: &Game<R>, mut out: W) -> Result<(), io::Error>
+fn double_draw<R, W: Write>(game: &Game<R>, view: &View, mut out: W) -> Result<(), io::Error>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let mut top_color = Color::Rgb { r: 0, g: 0, b: 0 };
-    let mut bottom_color = Color::Rgb { r: 0, g: 0, b: 0 };
+    let mut top_color = Color::Black;
+    let mut bottom_color = Color::Black;
     out.queue(MoveTo(0, 0))?
         .queue(SetBackgroundColor(top_color))?
         .flush()?;
 
-    let size = game.get_size();
-    for y_half in 0..size.height as i32 / 2 {
-        for x in 0..size.width as i32 {
-            let tc = game[(x, y_half * 2)].get_color().to();
-            let bc = game[(x, y_half * 2 + 1)].get_color().to();
+    let (width, _) = view.viewport.get_window();
+    for y_half in 0..view.lines() as i32 {
+        for x in 0..width as i32 {
+            let tc = view_color(game, view, x, y_half * 2);
+            let bc = view_color(game, view, x, y_half * 2 + 1);
 
             if top_color != tc {
                 top_color = tc;
@@ -143,32 +749,167 @@ where
     Ok(())
 }
 
-fn simple_draw<R, W: Write>(game: &Game<R>, mut out: W) -> Result<(), io::Error>
+/// Two by two or two by four cells per character, lit in the colour of the
+/// second palette state on the first.
+fn dots_draw<R, W: Write>(game: &Game<R>, view: &View, mut out: W) -> Result<(), io::Error>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    let palette = game.get_palette_colors();
+    out.queue(MoveTo(0, 0))?
+        .queue(SetBackgroundColor(view.color(palette[0])))?
+        .queue(SetForegroundColor(view.color(palette[1])))?;
+
+    let (dx, dy) = view.mode.dots();
+    let (dx, dy) = (dx as i32, dy as i32);
+    let (width, _) = view.viewport.get_window();
+    for row in 0..view.lines() as i32 {
+        for column in 0..width as i32 / dx {
+            let lit = (0..dx * dy)
+                .filter(|i| view.is_lit(game, palette[0], column * dx + i % dx, row * dy + i / dx))
+                .fold(0, |lit, i| lit | 1 << i);
+            out.queue(Print(view.mode.character(lit)))?;
+        }
+        out.queue(MoveToNextLine(1))?;
+    }
+
+    out.queue(SetBackgroundColor(Color::Black))?.flush()?;
+    Ok(())
+}
+
+fn simple_draw<R, W: Write>(game: &Game<R>, view: &View, mut out: W) -> Result<(), io::Error>
 where
     R: RuleSet,
     R::Data: ColoredDataType,
 {
-    let mut current_color = Color::Rgb { r: 0, g: 0, b: 0 };
+    let mut current_color = Color::Black;
     out.queue(MoveTo(0, 0))?
         .queue(SetBackgroundColor(current_color))?
         .flush()?;
 
-    for (i, cell) in game {
-        let color = cell.get_color().to();
-        if current_color != color {
-            current_color = color;
-            out.queue(&SetBackgroundColor(current_color))?;
-        }
-        out.queue(Print(' '))?;
-        if game.get_size().width as i32 == i.0 + 1 {
-            out.queue(MoveToNextLine(1))?;
+    let (width, height) = view.viewport.get_window();
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let color = view_color(game, view, x, y);
+            if current_color != color {
+                current_color = color;
+                out.queue(&SetBackgroundColor(current_color))?;
+            }
+            out.queue(Print(' '))?;
         }
+        out.queue(MoveToNextLine(1))?;
     }
     out.queue(SetBackgroundColor(Color::Black))?.flush()?;
 
     Ok(())
 }
 
+/// Two columns per cell with even rows pushed one column to the right, so
+/// the rows stagger like the hexagons they stand for.
+fn hex_draw<R, W: Write>(game: &Game<R>, view: &View, mut out: W) -> Result<(), io::Error>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    out.queue(MoveTo(0, 0))?;
+    let (width, height) = view.viewport.get_window();
+    for y in 0..height as i32 {
+        let mut current_color = Color::Black;
+        out.queue(SetBackgroundColor(current_color))?;
+        if view.is_indented(y) {
+            out.queue(Print(' '))?;
+        }
+        for x in 0..width as i32 {
+            let color = view_color(game, view, x, y);
+            if current_color != color {
+                current_color = color;
+                out.queue(SetBackgroundColor(current_color))?;
+            }
+            out.queue(Print("  "))?;
+        }
+        // Clears what is left of a wider row from a previous frame.
+        out.queue(SetBackgroundColor(Color::Black))?
+            .queue(Print(' '))?
+            .queue(MoveToNextLine(1))?;
+    }
+    out.flush()
+}
+
+/// One character per cell, pointing the same way as the triangle.
+fn triangle_draw<R, W: Write>(game: &Game<R>, view: &View, mut out: W) -> Result<(), io::Error>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    let mut current_color = Color::Black;
+    out.queue(MoveTo(0, 0))?
+        .queue(SetBackgroundColor(Color::Black))?
+        .queue(SetForegroundColor(current_color))?;
+    let (width, height) = view.viewport.get_window();
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let Some(cell) = view.cell(x, y) else {
+                out.queue(Print(' '))?;
+                continue;
+            };
+            let color = view.color(game.get_view_color(cell));
+            if current_color != color {
+                current_color = color;
+                out.queue(SetForegroundColor(current_color))?;
+            }
+            let up = (cell.0 + cell.1) % 2 == 0;
+            out.queue(Print(if up { '▲' } else { '▼' }))?;
+        }
+        out.queue(MoveToNextLine(1))?;
+    }
+    out.flush()
+}
+
+/// A character per cell in the cell's colour, two columns per cell with
+/// staggered rows in the hexagon layout.
+fn text_draw<R, W: Write>(
+    game: &Game<R>,
+    view: &View,
+    chars: Option<CellChar<R::Data>>,
+    mut out: W,
+) -> Result<(), io::Error>
+where
+    R: RuleSet,
+    R::Data: ColoredDataType,
+{
+    let staggered = view.layout == TextLayout::Staggered;
+    let mut current_color = None;
+    out.queue(MoveTo(0, 0))?.queue(ResetColor)?;
+    let (width, height) = view.viewport.get_window();
+    for y in 0..height as i32 {
+        if staggered && view.is_indented(y) {
+            out.queue(Print(' '))?;
+        }
+        for x in 0..width as i32 {
+            let c = match view.cell(x, y) {
+                Some(cell) => {
+                    let color = view.color(game.get_view_color(cell));
+                    if view.depth != ColorDepth::Mono && current_color != Some(color) {
+                        current_color = Some(color);
+                        out.queue(SetForegroundColor(color))?;
+                    }
+                    cell_char(game, chars, cell)
+                }
+                None => ' ',
+            };
+            out.queue(Print(c))?;
+            if staggered {
+                out.queue(Print(' '))?;
+            }
+        }
+        // Clears what is left of a wider row from a previous frame.
+        out.queue(Clear(ClearType::UntilNewLine))?
+            .queue(MoveToNextLine(1))?;
+    }
+    out.queue(ResetColor)?.flush()
+}
+
 struct BufferWriter<'b, W: Write> {
     buffer: &'b mut Vec<u8>,
     out: W,
@@ -198,16 +939,94 @@ impl<'b, W: Write> Drop for BufferWriter<'b, W> {
     }
 }
 
-trait ToColor {
-    fn to(self) -> Color;
+/// The colours a terminal can show.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    /// No colours, so cells are printed as characters.
+    Mono,
 }
 
-impl ToColor for DataColor {
-    fn to(self) -> Color {
-        Color::Rgb {
-            r: self.0,
-            g: self.1,
-            b: self.2,
+/// The 16 colours of the ANSI palette with xterm's values.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of each channel in the 6x6x6 colour cube of the 256 colours.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// `GOL_COLORS` set to `truecolor`, `256`, `16` or `mono`, or else a
+    /// guess from `NO_COLOR`, `COLORTERM` and `TERM`.
+    fn detect() -> ColorDepth {
+        let var = |name| std::env::var(name).unwrap_or_default().to_lowercase();
+        match var("GOL_COLORS").as_str() {
+            "truecolor" | "24bit" => return ColorDepth::TrueColor,
+            "256" => return ColorDepth::Ansi256,
+            "16" => return ColorDepth::Ansi16,
+            "mono" | "none" => return ColorDepth::Mono,
+            _ => {}
+        }
+        let term = var("TERM");
+        if std::env::var_os("NO_COLOR").is_some() || term == "dumb" {
+            ColorDepth::Mono
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            ColorDepth::TrueColor
+        } else if term.contains("256") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// The nearest colour the terminal has.
+    fn color(self, (r, g, b, _): DataColor) -> Color {
+        let distance = |(r2, g2, b2): (u8, u8, u8)| {
+            let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            d(r, r2) + d(g, g2) + d(b, b2)
+        };
+        match self {
+            ColorDepth::TrueColor => Color::Rgb { r, g, b },
+            ColorDepth::Ansi256 => {
+                let level = |c: u8| {
+                    (0..6)
+                        .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+                        .unwrap_or(0)
+                };
+                let (ri, gi, bi) = (level(r), level(g), level(b));
+                let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+                // The 24 greys run from 8 to 238.
+                let average = (r as u32 + g as u32 + b as u32) / 3;
+                let grey = (average.saturating_sub(3) / 10).min(23) as u8;
+                let level = 8 + grey * 10;
+                if distance((level, level, level)) < distance(cube) {
+                    Color::AnsiValue(232 + grey)
+                } else {
+                    Color::AnsiValue(16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8)
+                }
+            }
+            ColorDepth::Ansi16 => ANSI_COLORS
+                .iter()
+                .min_by_key(|(_, rgb)| distance(*rgb))
+                .map_or(Color::Reset, |&(color, _)| color),
+            ColorDepth::Mono => Color::Reset,
         }
     }
 }
@@ -0,0 +1,444 @@
+use std::any::Any;
+
+use crate::{GError, GResult, IndexType, Neighborhood, Size};
+
+/// How cells are arranged: which cells `Grid::get_area` hands to
+/// `RuleSet::next` and how the grid is drawn. Coordinates may fall outside
+/// the grid; `Grid` wraps them around.
+pub trait Topology: Any + Send + Sync {
+    /// Pushes the coordinates of the window around `index`, in the order
+    /// rules receive them. `size` is the rule's `source_size`.
+    fn window(&self, index: IndexType, size: u8, cells: &mut Vec<IndexType>);
+
+    /// Rejects grid sizes that cannot wrap around consistently.
+    fn check_size(&self, _width: u16, _height: u16) -> GResult<()> {
+        Ok(())
+    }
+
+    /// Turns one RGBA colour per cell into a picture of the grid.
+    fn draw(&self, colors: Vec<u8>, size: Size) -> (Vec<u8>, Size);
+
+    /// The cell drawn at point `(x, y)` of the picture made by `draw`, for a
+    /// grid of `size` cells. The default suits topologies drawn one pixel per
+    /// cell.
+    fn pick(&self, x: f32, y: f32, size: Size) -> Option<IndexType> {
+        let inside = x >= 0.0 && y >= 0.0;
+        let (x, y) = (x as u16, y as u16);
+        (inside && x < size.width && y < size.height).then_some((x as i32, y as i32))
+    }
+
+    fn text_layout(&self) -> TextLayout {
+        TextLayout::Square
+    }
+
+    /// Number of slices stacked top to bottom in the grid, such as the z
+    /// levels of a `Cubic` grid. Backends show one slice at a time.
+    fn slices(&self) -> u16 {
+        1
+    }
+}
+
+/// How the terminal backend lays out a topology.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextLayout {
+    /// One cell per character, or two stacked in one when space is short.
+    Square,
+    /// Two characters per cell with every other row indented by one.
+    Staggered,
+    /// One triangle character per cell, pointing up or down.
+    Triangles,
+}
+
+/// The usual grid: the window is the `size` x `size` square around a cell.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Square;
+
+impl Topology for Square {
+    fn window(&self, index: IndexType, size: u8, cells: &mut Vec<IndexType>) {
+        let half_size = (size / 2) as i32;
+        for y in index.1 - half_size..=index.1 + half_size {
+            for x in index.0 - half_size..=index.0 + half_size {
+                cells.push((x, y));
+            }
+        }
+    }
+
+    fn draw(&self, colors: Vec<u8>, size: Size) -> (Vec<u8>, Size) {
+        (colors, size)
+    }
+}
+
+/// Hexagons stored row by row with odd rows shifted half a cell to the
+/// left. The window uses the skewed layout of Golly's hexagonal rules: the
+/// six neighbours are the cells of the 3x3 window except the top-right and
+/// bottom-left corners.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Hexagonal;
+
+impl Topology for Hexagonal {
+    fn window(&self, index: IndexType, size: u8, cells: &mut Vec<IndexType>) {
+        let (x, y) = index;
+        let half_size = (size / 2) as i32;
+        for h in y - half_size..=y + half_size {
+            // Going down a row moves the skewed window half a cell to the
+            // left, which is a whole cell every other row.
+            let shift = y.div_euclid(2) - h.div_euclid(2);
+            for w in x - half_size..=x + half_size {
+                cells.push((w + shift, h));
+            }
+        }
+    }
+
+    /// Rows only keep alternating across the wrap with an even height.
+    fn check_size(&self, _width: u16, height: u16) -> GResult<()> {
+        if !height.is_multiple_of(2) {
+            return Err(GError::TopologyError(
+                "hexagonal grids need an even height".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Tiles 4x4 pixel hexagons three pixel rows apart.
+    fn draw(&self, colors: Vec<u8>, size: Size) -> (Vec<u8>, Size) {
+        // Each hexagon is two pixels wide on its first and last row and four
+        // in between, so neighbouring rows interlock.
+        const SHAPE: [(usize, usize); 4] = [(1, 3), (0, 4), (0, 4), (1, 3)];
+        let (width, height) = (size.width as usize, size.height as usize);
+        let image_width = width * 4 + 2;
+        let image_height = height * 3 + 1;
+        let mut v = [0, 0, 0, 255].repeat(image_width * image_height);
+        for y in 0..height {
+            let shift = if y % 2 == 0 { 2 } else { 0 };
+            for x in 0..width {
+                let color = &colors[(y * width + x) * 4..][..4];
+                for (dy, &(start, end)) in SHAPE.iter().enumerate() {
+                    let row = (y * 3 + dy) * image_width + x * 4 + shift;
+                    for i in row + start..row + end {
+                        v[i * 4..i * 4 + 4].copy_from_slice(color);
+                    }
+                }
+            }
+        }
+        let size = Size {
+            width: image_width as u16,
+            height: image_height as u16,
+        };
+        (v, size)
+    }
+
+    /// Rows of hexagons overlap by one pixel row, which goes to the lower
+    /// one.
+    fn pick(&self, x: f32, y: f32, size: Size) -> Option<IndexType> {
+        if y < 0.0 {
+            return None;
+        }
+        let row = ((y / 3.0) as u16).min(size.height.saturating_sub(1));
+        let shift = if row.is_multiple_of(2) { 2.0 } else { 0.0 };
+        Square.pick((x - shift) / 4.0, row as f32, size)
+    }
+
+    fn text_layout(&self) -> TextLayout {
+        TextLayout::Staggered
+    }
+}
+
+const TRIANGLE_HALF_BASE: usize = 4;
+const TRIANGLE_ROW: usize = 7;
+
+/// Triangles alternating between pointing up and down along each row, the
+/// cell at `(x, y)` pointing up when `x + y` is even.
+///
+/// The window ignores the rule's `source_size`: it starts with the cell
+/// itself, followed by its neighbours as if the cell pointed up, so rules
+/// need not care about orientation.
+/// `VonNeumann` gives the three edge neighbours as left, right and below.
+/// `Moore` gives all twelve triangles sharing a corner: the four in the same
+/// row from left to right, the three towards the apex and the five along
+/// the base.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Triangular {
+    VonNeumann,
+    Moore,
+}
+
+impl Triangular {
+    pub fn neighbour_count(&self) -> usize {
+        match self {
+            Triangular::VonNeumann => 3,
+            Triangular::Moore => 12,
+        }
+    }
+}
+
+impl Topology for Triangular {
+    fn window(&self, index: IndexType, _size: u8, cells: &mut Vec<IndexType>) {
+        let (x, y) = index;
+        // Towards the base, which is below for triangles pointing up.
+        let down = if (x + y).rem_euclid(2) == 0 { 1 } else { -1 };
+        cells.push((x, y));
+        match self {
+            Triangular::VonNeumann => {
+                cells.extend([(x - 1, y), (x + 1, y), (x, y + down)]);
+            }
+            Triangular::Moore => {
+                cells.extend([(x - 2, y), (x - 1, y), (x + 1, y), (x + 2, y)]);
+                cells.extend((x - 1..=x + 1).map(|w| (w, y - down)));
+                cells.extend((x - 2..=x + 2).map(|w| (w, y + down)));
+            }
+        }
+    }
+
+    /// Orientations only alternate across the wrap with an even width and
+    /// height.
+    fn check_size(&self, width: u16, height: u16) -> GResult<()> {
+        if !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+            return Err(GError::TopologyError(
+                "triangular grids need an even width and height".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Triangles 8 pixels wide and 7 high, each overlapping its neighbours
+    /// in the row by half its width.
+    fn draw(&self, colors: Vec<u8>, size: Size) -> (Vec<u8>, Size) {
+        let width = size.width as usize;
+        let image_width = (width + 1) * TRIANGLE_HALF_BASE;
+        let image_height = size.height as usize * TRIANGLE_ROW;
+        let mut v = [0, 0, 0, 255].repeat(image_width * image_height);
+        for py in 0..image_height {
+            for px in 0..image_width {
+                let cell = self.pick(px as f32 + 0.5, py as f32 + 0.5, size);
+                if let Some((x, y)) = cell {
+                    let i = (py * image_width + px) * 4;
+                    let cell = (y as usize * width + x as usize) * 4;
+                    v[i..i + 4].copy_from_slice(&colors[cell..][..4]);
+                }
+            }
+        }
+        let size = Size {
+            width: image_width as u16,
+            height: image_height as u16,
+        };
+        (v, size)
+    }
+
+    fn pick(&self, x: f32, y: f32, size: Size) -> Option<IndexType> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let row = y / TRIANGLE_ROW as f32;
+        // Distance from the top of the row, between 0 and 1.
+        let (y, t) = (row as usize, row.fract());
+        let u = x / TRIANGLE_HALF_BASE as f32;
+        let (k, f) = (u as usize, u.fract());
+        // The point lies in triangle `k` or the one before it; the edge
+        // between them runs from the top corner to the bottom one.
+        let edge = if (k + y).is_multiple_of(2) {
+            1.0 - t
+        } else {
+            t
+        };
+        let x = if f >= edge { Some(k) } else { k.checked_sub(1) };
+        x.filter(|&x| x < size.width as usize && y < size.height as usize)
+            .map(|x| (x as i32, y as i32))
+    }
+
+    fn text_layout(&self) -> TextLayout {
+        TextLayout::Triangles
+    }
+}
+
+/// A three-dimensional grid of `width` x `height` x `depth` cells, stored
+/// as its z slices stacked top to bottom, so level `z` takes up rows
+/// `z * height` to `(z + 1) * height`. Every direction wraps around.
+///
+/// Like `Triangular`, the window starts with the cell itself, followed by
+/// its neighbours within `source_size / 2` (at least one) ordered by z, y
+/// and x. Moore neighbourhoods are cubes and von Neumann ones octahedra,
+/// giving 26 and 6 neighbours at range one.
+#[derive(Clone, Copy, Debug)]
+pub struct Cubic {
+    width: u16,
+    height: u16,
+    depth: u16,
+    neighborhood: Neighborhood,
+}
+
+impl Cubic {
+    pub fn new(size: (u16, u16, u16), neighborhood: Neighborhood) -> GResult<Cubic> {
+        if !matches!(neighborhood, Neighborhood::Moore | Neighborhood::VonNeumann) {
+            return Err(GError::TopologyError(
+                "cubic grids need a Moore or von Neumann neighbourhood".to_string(),
+            ));
+        }
+        Ok(Cubic {
+            width: size.0,
+            height: size.1,
+            depth: size.2,
+            neighborhood,
+        })
+    }
+
+    /// Width and height of the two-dimensional grid holding the cells.
+    pub fn grid_size(&self) -> (u16, u16) {
+        (self.width, self.height * self.depth)
+    }
+
+    /// Position in the two-dimensional grid of the cell at `(x, y, z)`.
+    pub fn index(&self, x: i32, y: i32, z: i32) -> IndexType {
+        let y = y.rem_euclid(self.height as i32);
+        let z = z.rem_euclid(self.depth as i32);
+        (x.rem_euclid(self.width as i32), z * self.height as i32 + y)
+    }
+
+    /// Inverse of `index`.
+    pub fn position(&self, index: IndexType) -> (i32, i32, i32) {
+        let height = self.height as i32;
+        (index.0, index.1 % height, index.1 / height)
+    }
+}
+
+impl Topology for Cubic {
+    fn window(&self, index: IndexType, size: u8, cells: &mut Vec<IndexType>) {
+        let (x, y, z) = self.position(index);
+        let range = (size as i32 / 2).max(1);
+        cells.push(index);
+        for dz in -range..=range {
+            for dy in -range..=range {
+                for dx in -range..=range {
+                    let inside = match self.neighborhood {
+                        Neighborhood::VonNeumann => dx.abs() + dy.abs() + dz.abs() <= range,
+                        _ => true,
+                    };
+                    if inside && (dx, dy, dz) != (0, 0, 0) {
+                        cells.push(self.index(x + dx, y + dy, z + dz));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_size(&self, width: u16, height: u16) -> GResult<()> {
+        if (width, height) != self.grid_size() {
+            return Err(GError::TopologyError(format!(
+                "a {}x{}x{} cubic grid must be stored {} wide and {} high",
+                self.width,
+                self.height,
+                self.depth,
+                self.grid_size().0,
+                self.grid_size().1
+            )));
+        }
+        Ok(())
+    }
+
+    fn draw(&self, colors: Vec<u8>, size: Size) -> (Vec<u8>, Size) {
+        (colors, size)
+    }
+
+    fn slices(&self) -> u16 {
+        self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window<T: Topology>(topology: &T, index: IndexType, size: u8) -> Vec<IndexType> {
+        let mut cells = Vec::new();
+        topology.window(index, size, &mut cells);
+        cells
+    }
+
+    /// The six cells of the skewed 3x3 window that are hexagon neighbours.
+    fn hex_neighbours(index: IndexType) -> Vec<IndexType> {
+        let cells = window(&Hexagonal, index, 3);
+        [0, 1, 3, 5, 7, 8].iter().map(|&i| cells[i]).collect()
+    }
+
+    #[test]
+    fn hexagonal_window_skews_with_the_rows() {
+        // Odd rows sit half a cell to the left, so the rows above and below
+        // an even row touch it on the right and those of an odd row on the
+        // left.
+        let even = [(4, 3), (5, 3), (3, 4), (5, 4), (4, 5), (5, 5)];
+        assert_eq!(hex_neighbours((4, 4)), even);
+        let odd = [(3, 4), (4, 4), (3, 5), (5, 5), (3, 6), (4, 6)];
+        assert_eq!(hex_neighbours((4, 5)), odd);
+        for y in 0..4 {
+            for x in 0..4 {
+                for n in hex_neighbours((x, y)) {
+                    assert!(hex_neighbours(n).contains(&(x, y)), "{:?} {:?}", (x, y), n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn triangular_windows_face_the_base() {
+        // (4, 4) points up, so its base is below, and (5, 4) points down.
+        let up = window(&Triangular::VonNeumann, (4, 4), 3);
+        assert_eq!(up, [(4, 4), (3, 4), (5, 4), (4, 5)]);
+        let down = window(&Triangular::VonNeumann, (5, 4), 3);
+        assert_eq!(down, [(5, 4), (4, 4), (6, 4), (5, 3)]);
+
+        let up = window(&Triangular::Moore, (4, 4), 3);
+        let row = [(2, 4), (3, 4), (5, 4), (6, 4)];
+        let apex = [(3, 3), (4, 3), (5, 3)];
+        let base = [(2, 5), (3, 5), (4, 5), (5, 5), (6, 5)];
+        assert_eq!(up[0], (4, 4));
+        assert_eq!(up[1..], [&row[..], &apex, &base].concat());
+        let down = window(&Triangular::Moore, (5, 4), 3);
+        assert_eq!(down[8..], [(3, 3), (4, 3), (5, 3), (6, 3), (7, 3)]);
+
+        for topology in [Triangular::VonNeumann, Triangular::Moore] {
+            for y in 0..4 {
+                for x in 0..4 {
+                    let cells = window(&topology, (x, y), 3);
+                    assert_eq!(cells.len(), topology.neighbour_count() + 1);
+                    for &n in &cells[1..] {
+                        assert!(window(&topology, n, 3)[1..].contains(&(x, y)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cubic_windows_reach_every_neighbour() {
+        let cubic = Cubic::new((4, 4, 4), Neighborhood::Moore).unwrap();
+        let corner = cubic.index(0, 0, 0);
+        let cells = window(&cubic, corner, 3);
+        assert_eq!(cells.len(), 27);
+        assert_eq!(cells[0], corner);
+        let mut offsets: Vec<(i32, i32, i32)> = cells[1..]
+            .iter()
+            .map(|&c| cubic.position(c))
+            .map(|(x, y, z)| ((x + 1) % 4 - 1, (y + 1) % 4 - 1, (z + 1) % 4 - 1))
+            .collect();
+        offsets.sort();
+        offsets.dedup();
+        assert_eq!(offsets.len(), 26);
+        assert!(offsets
+            .iter()
+            .all(|&(x, y, z)| x.abs().max(y.abs()).max(z.abs()) == 1));
+
+        let cubic = Cubic::new((4, 4, 4), Neighborhood::VonNeumann).unwrap();
+        let faces: Vec<(i32, i32, i32)> = window(&cubic, cubic.index(1, 2, 3), 3)[1..]
+            .iter()
+            .map(|&c| cubic.position(c))
+            .collect();
+        let expected = [
+            (1, 2, 2),
+            (1, 1, 3),
+            (0, 2, 3),
+            (2, 2, 3),
+            (1, 3, 3),
+            (1, 2, 0),
+        ];
+        assert_eq!(faces, expected);
+    }
+}
@@ -0,0 +1,134 @@
+use std::any::Any;
+
+use crate::{GError, GResult, LifeCell, RuleSet, Topology, Triangular};
+
+/// Outer totalistic Life for the `Triangular` topology, where the window
+/// holds the cell followed by its 3 or 12 neighbours.
+#[derive(Clone)]
+pub struct TriangularLife {
+    // Bit n is set when n live neighbours give birth or survival.
+    birth: u16,
+    survival: u16,
+}
+
+impl Default for TriangularLife {
+    fn default() -> Self {
+        TriangularLife::parse("B4/S345").unwrap()
+    }
+}
+
+impl TriangularLife {
+    /// Reads `B4/S345`. Counts above 9 are written with commas, as in
+    /// `B4,5/S3,4,10`.
+    pub fn parse(rule: &str) -> GResult<TriangularLife> {
+        let err = |message: &str| GError::RuleStringError {
+            rule: rule.to_string(),
+            message: message.to_string(),
+        };
+        let (mut birth, mut survival) = (None, None);
+        for part in rule.trim().split('/') {
+            let part = part.trim().to_ascii_uppercase();
+            let (target, counts) = match part.chars().next() {
+                Some('B') => (&mut birth, &part[1..]),
+                Some('S') => (&mut survival, &part[1..]),
+                _ => return Err(err("expected B and S parts")),
+            };
+            let numbers: Vec<&str> = if counts.contains(',') {
+                counts.split(',').collect()
+            } else {
+                counts
+                    .char_indices()
+                    .map(|(i, c)| &counts[i..i + c.len_utf8()])
+                    .collect()
+            };
+            let mut mask = 0u16;
+            for n in numbers.iter().filter(|n| !n.is_empty()) {
+                match n.trim().parse::<u8>() {
+                    Ok(n) if n <= 12 => mask |= 1 << n,
+                    _ => return Err(err("counts must be between 0 and 12")),
+                }
+            }
+            *target = Some(mask);
+        }
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(TriangularLife { birth, survival }),
+            _ => Err(err("expected B and S parts")),
+        }
+    }
+}
+
+impl RuleSet for TriangularLife {
+    type Data = LifeCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        let alive = source[1..].iter().filter(|c| c.is_alive()).count();
+        let mask = if source[0].is_alive() {
+            self.survival
+        } else {
+            self.birth
+        };
+        LifeCell::from(mask >> alive & 1 == 1)
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![LifeCell::from(false), LifeCell::from(true)]
+    }
+
+    fn check_topology(&self, topology: &dyn Topology) -> GResult<()> {
+        let topology: &dyn Any = topology;
+        if topology.downcast_ref::<Triangular>().is_none() {
+            return Err(GError::TopologyError(
+                "triangular Life needs a Triangular topology".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Hexagonal, IndexType};
+
+    fn game(rule: TriangularLife) -> Game<TriangularLife> {
+        let data = vec![LifeCell::from(false); 64];
+        Game::init_with_rules(rule, data, 8)
+            .unwrap()
+            .with_topology(Triangular::Moore)
+            .unwrap()
+    }
+
+    #[test]
+    fn hexagon_of_triangles_is_still() {
+        // The six triangles around a corner each touch the five others, and
+        // no empty triangle touches four of them.
+        let mut game = game(TriangularLife::default());
+        let hexagon = [(2, 2), (3, 2), (4, 2), (2, 3), (3, 3), (4, 3)];
+        for &c in &hexagon {
+            game.toggle(c);
+        }
+        for _ in 0..3 {
+            game.next_step();
+            let alive: Vec<IndexType> = (0..8)
+                .flat_map(|y| (0..8).map(move |x| (x, y)))
+                .filter(|&c| game.get_view_cell(c).is_alive())
+                .collect();
+            assert_eq!(alive, hexagon);
+        }
+    }
+
+    #[test]
+    fn needs_a_triangular_grid() {
+        let data = vec![LifeCell::from(false); 64];
+        let square = Game::init_with_rules(TriangularLife::default(), data, 8).unwrap();
+        assert!(TriangularLife::default()
+            .check_topology(square.get_topology())
+            .is_err());
+        assert!(square.with_topology(Hexagonal).is_err());
+        let triangular = game(TriangularLife::default());
+        assert!(TriangularLife::default()
+            .check_topology(triangular.get_topology())
+            .is_ok());
+    }
+}
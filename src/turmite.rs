@@ -0,0 +1,407 @@
+use std::sync::Arc;
+
+use crate::{Agent, Color, ColoredDataType, DataType, GError, GResult, Grid, RuleSet};
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct TurmiteCell {
+    state: u8,
+}
+
+impl TurmiteCell {
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+}
+
+impl From<u8> for TurmiteCell {
+    fn from(state: u8) -> Self {
+        TurmiteCell { state }
+    }
+}
+
+impl DataType for TurmiteCell {}
+
+impl ColoredDataType for TurmiteCell {
+    /// Black and white for the first two states, then steps around the hue
+    /// circle.
+    fn get_color(&self) -> Color {
+        match self.state {
+            0 => (0, 0, 0, 255),
+            1 => (255, 255, 255, 255),
+            s => {
+                let hue = ((s - 2) as f32 * 0.38).fract() * 6.0;
+                let f = hue.fract();
+                let (r, g, b) = match hue as u8 {
+                    0 => (1.0, f, 0.0),
+                    1 => (1.0 - f, 1.0, 0.0),
+                    2 => (0.0, 1.0, f),
+                    3 => (0.0, 1.0 - f, 1.0),
+                    4 => (f, 0.0, 1.0),
+                    _ => (1.0, 0.0, 1.0 - f),
+                };
+                ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255)
+            }
+        }
+    }
+}
+
+/// Cells stay as they are, so only agents change the grid.
+#[derive(Clone, Default)]
+pub struct TurmiteWorld {}
+
+impl RuleSet for TurmiteWorld {
+    type Data = TurmiteCell;
+    const SOURCE_SIZE: u8 = 1;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        source[0].clone()
+    }
+
+    fn next_generation(
+        &self,
+        grid: &Grid<Self::Data>,
+        _generation: u64,
+    ) -> Option<Vec<Self::Data>> {
+        Some(grid.get_raw_data().to_vec())
+    }
+
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![TurmiteCell::from(0), TurmiteCell::from(1)]
+    }
+
+    fn supports_cell_updates(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Turn {
+    None,
+    Right,
+    UTurn,
+    Left,
+}
+
+impl Turn {
+    fn quarters(self) -> u8 {
+        match self {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::UTurn => 2,
+            Turn::Left => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    write: u8,
+    turn: Turn,
+    next_state: u8,
+}
+
+/// A turmite: on each step it looks up its own state and the colour under
+/// it, writes a new colour, turns and moves one cell forward.
+pub struct Turmite {
+    x: i32,
+    y: i32,
+    // 0 north, 1 east, 2 south, 3 west
+    direction: u8,
+    state: u8,
+    // table[state][colour]
+    table: Arc<Vec<Vec<Transition>>>,
+    color: Color,
+}
+
+impl Turmite {
+    /// Langton's ant generalised to a string of turns such as `RL` or
+    /// `LLRR`: the cell colour selects the turn and is then advanced by one.
+    /// `N` means no turn and `U` a U-turn.
+    pub fn from_turns(turns: &str, position: (u16, u16)) -> GResult<Turmite> {
+        let err = |message: String| GError::RuleStringError {
+            rule: turns.to_string(),
+            message,
+        };
+        let turns = turns
+            .trim()
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'L' => Ok(Turn::Left),
+                'R' => Ok(Turn::Right),
+                'N' => Ok(Turn::None),
+                'U' => Ok(Turn::UTurn),
+                c => Err(err(format!("unknown turn '{}'", c))),
+            })
+            .collect::<GResult<Vec<_>>>()?;
+        if turns.len() < 2 || turns.len() > 256 {
+            return Err(err("needs between 2 and 256 turns".to_string()));
+        }
+        let colors = turns.len();
+        let row = turns
+            .iter()
+            .enumerate()
+            .map(|(c, &turn)| Transition {
+                write: ((c + 1) % colors) as u8,
+                turn,
+                next_state: 0,
+            })
+            .collect();
+        Ok(Turmite::with_table(vec![row], position))
+    }
+
+    /// Parses the usual turmite notation `{{{1, 2, 0}, {0, 8, 0}}}`, one
+    /// `{write, turn, next state}` triple per state and colour, where turn
+    /// is 1 for none, 2 right, 4 U-turn and 8 left.
+    pub fn from_table(table: &str, position: (u16, u16)) -> GResult<Turmite> {
+        let err = |message: String| GError::RuleStringError {
+            rule: table.to_string(),
+            message,
+        };
+        let value = Nested::parse(table).map_err(err)?;
+        let states = value.list().map_err(err)?;
+        let mut rows = Vec::with_capacity(states.len());
+        for state in states {
+            let colors = state.list().map_err(err)?;
+            let row = colors
+                .iter()
+                .map(|t| match t.numbers().map_err(err)?[..] {
+                    [write, turn, next_state] => Ok((write, turn, next_state)),
+                    _ => Err(err("transitions need three numbers".to_string())),
+                })
+                .collect::<GResult<Vec<_>>>()?;
+            rows.push(row);
+        }
+        let colors = rows.first().map_or(0, Vec::len);
+        if rows.is_empty() || rows.iter().any(|r| r.len() != colors) {
+            return Err(err(
+                "every state needs the same number of colours".to_string()
+            ));
+        }
+        if colors > 256 || rows.len() > 256 {
+            return Err(err("at most 256 states and colours".to_string()));
+        }
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&(write, turn, next_state)| {
+                        if write as usize >= colors {
+                            return Err(err(format!("colour {} out of range", write)));
+                        }
+                        if next_state as usize >= rows.len() {
+                            return Err(err(format!("state {} out of range", next_state)));
+                        }
+                        let turn = match turn {
+                            1 => Turn::None,
+                            2 => Turn::Right,
+                            4 => Turn::UTurn,
+                            8 => Turn::Left,
+                            t => return Err(err(format!("unknown turn {}", t))),
+                        };
+                        Ok(Transition {
+                            write: write as u8,
+                            turn,
+                            next_state: next_state as u8,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<GResult<Vec<_>>>()?;
+        Ok(Turmite::with_table(rows, position))
+    }
+
+    fn with_table(table: Vec<Vec<Transition>>, position: (u16, u16)) -> Turmite {
+        Turmite {
+            x: position.0 as i32,
+            y: position.1 as i32,
+            direction: 0,
+            state: 0,
+            table: Arc::new(table),
+            color: (255, 0, 0, 255),
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Turmite {
+        self.color = color;
+        self
+    }
+
+    /// 0 faces north, 1 east, 2 south and 3 west.
+    pub fn with_direction(mut self, direction: u8) -> Turmite {
+        self.direction = direction % 4;
+        self
+    }
+
+    pub fn colors(&self) -> usize {
+        self.table[0].len()
+    }
+}
+
+impl Agent<TurmiteCell> for Turmite {
+    fn step(&mut self, grid: &mut Grid<TurmiteCell>) {
+        let size = grid.get_size();
+        self.x = self.x.rem_euclid(size.width as i32);
+        self.y = self.y.rem_euclid(size.height as i32);
+        let cell = &mut grid[(self.x, self.y)];
+        let colors = self.table[0].len();
+        let t = self.table[self.state as usize][cell.state as usize % colors];
+        cell.state = t.write;
+        self.state = t.next_state;
+        self.direction = (self.direction + t.turn.quarters()) % 4;
+        let (dx, dy) = match self.direction {
+            0 => (0, -1),
+            1 => (1, 0),
+            2 => (0, 1),
+            _ => (-1, 0),
+        };
+        self.x = (self.x + dx).rem_euclid(size.width as i32);
+        self.y = (self.y + dy).rem_euclid(size.height as i32);
+    }
+
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+enum Nested {
+    Number(u32),
+    List(Vec<Nested>),
+}
+
+impl Nested {
+    fn parse(text: &str) -> Result<Nested, String> {
+        let mut chars = text.chars().filter(|c| !c.is_whitespace()).peekable();
+        let value = Nested::parse_value(&mut chars)?;
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{}' after the table", c)),
+        }
+    }
+
+    fn parse_value<I: Iterator<Item = char>>(
+        chars: &mut std::iter::Peekable<I>,
+    ) -> Result<Nested, String> {
+        if chars.next_if_eq(&'{').is_some() {
+            let mut items = Vec::new();
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Nested::List(items));
+            }
+            loop {
+                items.push(Nested::parse_value(chars)?);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(Nested::List(items)),
+                    Some(c) => return Err(format!("unexpected '{}'", c)),
+                    None => return Err("missing '}'".to_string()),
+                }
+            }
+        }
+        let mut digits = String::new();
+        while let Some(d) = chars.next_if(char::is_ascii_digit) {
+            digits.push(d);
+        }
+        digits
+            .parse()
+            .map(Nested::Number)
+            .map_err(|_| "expected a number or '{'".to_string())
+    }
+
+    fn list(&self) -> Result<&[Nested], String> {
+        match self {
+            Nested::List(items) => Ok(items),
+            Nested::Number(_) => Err("expected '{'".to_string()),
+        }
+    }
+
+    fn numbers(&self) -> Result<Vec<u32>, String> {
+        self.list()?
+            .iter()
+            .map(|n| match n {
+                Nested::Number(n) => Ok(*n),
+                Nested::List(_) => Err("expected a number".to_string()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty(size: u16) -> Grid<TurmiteCell> {
+        let data = vec![TurmiteCell::default(); size as usize * size as usize];
+        Grid::init_with_data(data, size).unwrap()
+    }
+
+    #[test]
+    fn langtons_ant_first_steps() {
+        let mut grid = empty(20);
+        let mut ant = Turmite::from_turns("RL", (10, 10)).unwrap();
+        let mut path = Vec::new();
+        for _ in 0..5 {
+            ant.step(&mut grid);
+            path.push(ant.position());
+        }
+        assert_eq!(path, vec![(11, 10), (11, 11), (10, 11), (10, 10), (9, 10)]);
+        assert_eq!(grid[(10, 10)].state(), 0);
+        assert_eq!(grid[(11, 10)].state(), 1);
+        assert_eq!(grid[(11, 11)].state(), 1);
+        assert_eq!(grid[(10, 11)].state(), 1);
+    }
+
+    #[test]
+    fn langtons_ant_builds_the_highway() {
+        let mut grid = empty(200);
+        let mut ant = Turmite::from_turns("RL", (100, 100)).unwrap();
+        for _ in 0..11000 {
+            ant.step(&mut grid);
+        }
+        // the highway repeats every 104 steps, two cells further diagonally
+        let mut positions = vec![ant.position()];
+        for _ in 0..3 {
+            for _ in 0..104 {
+                ant.step(&mut grid);
+            }
+            positions.push(ant.position());
+        }
+        let moves: Vec<(i32, i32)> = positions
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1))
+            .collect();
+        assert!(moves.iter().all(|&m| m == moves[0]));
+        assert_eq!((moves[0].0.abs(), moves[0].1.abs()), (2, 2));
+    }
+
+    #[test]
+    fn table_matches_turns() {
+        let (mut a, mut b) = (empty(30), empty(30));
+        let mut from_turns = Turmite::from_turns("RL", (15, 15)).unwrap();
+        let mut from_table = Turmite::from_table("{{{1, 2, 0}, {0, 8, 0}}}", (15, 15)).unwrap();
+        for _ in 0..300 {
+            from_turns.step(&mut a);
+            from_table.step(&mut b);
+            assert_eq!(from_turns.position(), from_table.position());
+        }
+        assert!(a.get_raw_data() == b.get_raw_data());
+    }
+
+    #[test]
+    fn rejects_bad_tables() {
+        for table in [
+            "{{{2, 2, 0}, {0, 8, 0}}}",
+            "{{{257, 2, 0}, {0, 8, 0}}}",
+            "{{{1, 2, 1}, {0, 8, 0}}}",
+            "{{{1, 2, 256}, {0, 8, 0}}}",
+            "{{{1, 3, 0}, {0, 8, 0}}}",
+            "{{{1, 2, 0}, {0, 8, 0}}, {{1, 2, 0}}}",
+            "{{{1, 2}, {0, 8, 0}}}",
+            "{{{1, 2, 0}, {0, 8, 0}}",
+        ] {
+            assert!(Turmite::from_table(table, (0, 0)).is_err(), "{}", table);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{ColoredDataType, Game, IndexType, RuleSet, Size};
+
+/// Zoom levels are powers of two between these.
+const MIN_ZOOM: f32 = 1.0 / 64.0;
+const MAX_ZOOM: f32 = 64.0;
+
+/// The part of a game image shown in a window, which backends pan and zoom
+/// instead of stretching the whole image over the window. Points are either
+/// in window pixels or in pixels of the image from `Game::to_image`.
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    image: Size,
+    window: (f32, f32),
+    /// Image point at the top-left corner of the window.
+    corner: (f32, f32),
+    /// Window pixels per image pixel.
+    zoom: f32,
+    zoom_range: (f32, f32),
+}
+
+impl Viewport {
+    /// Starts out showing the whole image.
+    pub fn new(image: Size, window: (f32, f32)) -> Viewport {
+        let mut viewport = Viewport {
+            image,
+            window,
+            corner: (0.0, 0.0),
+            zoom: 1.0,
+            zoom_range: (MIN_ZOOM, MAX_ZOOM),
+        };
+        viewport.fit_all();
+        viewport
+    }
+
+    /// Limits the zoom, for backends that cannot draw every zoom level.
+    pub fn with_zoom_range(mut self, min: f32, max: f32) -> Viewport {
+        self.zoom_range = (min, max);
+        self.set_zoom(self.zoom, self.center());
+        self
+    }
+
+    /// Changes the window size, keeping the top-left corner in place.
+    pub fn set_window(&mut self, window: (f32, f32)) {
+        self.window = window;
+    }
+
+    pub fn get_window(&self) -> (f32, f32) {
+        self.window
+    }
+
+    pub fn get_zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Image point at the top-left corner of the window.
+    pub fn get_corner(&self) -> (f32, f32) {
+        self.corner
+    }
+
+    /// Size of the image area the window shows.
+    pub fn get_visible_size(&self) -> (f32, f32) {
+        (self.window.0 / self.zoom, self.window.1 / self.zoom)
+    }
+
+    /// Middle of the window, in window pixels.
+    pub fn center(&self) -> (f32, f32) {
+        (self.window.0 / 2.0, self.window.1 / 2.0)
+    }
+
+    pub fn to_image(&self, point: (f32, f32)) -> (f32, f32) {
+        (
+            self.corner.0 + point.0 / self.zoom,
+            self.corner.1 + point.1 / self.zoom,
+        )
+    }
+
+    pub fn to_window(&self, point: (f32, f32)) -> (f32, f32) {
+        (
+            (point.0 - self.corner.0) * self.zoom,
+            (point.1 - self.corner.1) * self.zoom,
+        )
+    }
+
+    /// The image pixel under a window point, if the point is on the image.
+    pub fn pixel_at(&self, point: (f32, f32)) -> Option<IndexType> {
+        let (x, y) = self.to_image(point);
+        let (x, y) = (x.floor(), y.floor());
+        let inside =
+            x >= 0.0 && y >= 0.0 && x < self.image.width as f32 && y < self.image.height as f32;
+        inside.then_some((x as i32, y as i32))
+    }
+
+    /// Moves the view by a distance in window pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.corner.0 -= dx / self.zoom;
+        self.corner.1 -= dy / self.zoom;
+    }
+
+    /// Moves the view an eighth of the window per step, towards the right
+    /// and the bottom for positive steps.
+    pub fn pan_steps(&mut self, x: i32, y: i32) {
+        let (dx, dy) = (self.window.0 / 8.0, self.window.1 / 8.0);
+        self.pan(-x as f32 * dx, -y as f32 * dy);
+    }
+
+    /// Sets the zoom, keeping the image point under `anchor` in place.
+    pub fn set_zoom(&mut self, zoom: f32, anchor: (f32, f32)) {
+        let fixed = self.to_image(anchor);
+        self.zoom = zoom.clamp(self.zoom_range.0, self.zoom_range.1);
+        self.corner = (
+            fixed.0 - anchor.0 / self.zoom,
+            fixed.1 - anchor.1 / self.zoom,
+        );
+    }
+
+    /// Goes to the next zoom level up, keeping the image point under
+    /// `anchor` in place.
+    pub fn zoom_in(&mut self, anchor: (f32, f32)) {
+        let level = self.zoom.log2().floor() + 1.0;
+        self.set_zoom(level.exp2(), anchor);
+    }
+
+    pub fn zoom_out(&mut self, anchor: (f32, f32)) {
+        let level = self.zoom.log2().ceil() - 1.0;
+        self.set_zoom(level.exp2(), anchor);
+    }
+
+    /// Rounds the zoom down to a zoom level, so every image pixel covers
+    /// the same number of window pixels.
+    pub fn snap_zoom(&mut self) {
+        let level = self.zoom.log2().floor();
+        self.set_zoom(level.exp2(), self.center());
+    }
+
+    /// Shows the image area starting at `corner`, as large as the window
+    /// allows and centred.
+    pub fn fit(&mut self, corner: (f32, f32), size: (f32, f32)) {
+        let zoom = (self.window.0 / size.0).min(self.window.1 / size.1);
+        self.zoom = zoom.clamp(self.zoom_range.0, self.zoom_range.1);
+        let (width, height) = self.get_visible_size();
+        self.corner = (
+            corner.0 + (size.0 - width) / 2.0,
+            corner.1 + (size.1 - height) / 2.0,
+        );
+    }
+
+    pub fn fit_all(&mut self) {
+        let size = (self.image.width as f32, self.image.height as f32);
+        self.fit((0.0, 0.0), size);
+    }
+
+    /// Shows the cells of the slice shown that are not in the empty state,
+    /// with a cell of margin around them, or the whole image when there are
+    /// none. Topologies draw cells in rows and columns, so cells are taken
+    /// to spread evenly over the image.
+    pub fn fit_pattern<R>(&mut self, game: &Game<R>)
+    where
+        R: RuleSet,
+        R::Data: ColoredDataType,
+    {
+        let Some(((x0, y0), (x1, y1))) = game.pattern_bounds() else {
+            self.fit_all();
+            return;
+        };
+        let view = game.get_view_size();
+        let cell = (
+            self.image.width as f32 / view.width as f32,
+            self.image.height as f32 / view.height as f32,
+        );
+        let corner = ((x0 - 1) as f32 * cell.0, (y0 - 1) as f32 * cell.1);
+        let size = ((x1 - x0 + 3) as f32 * cell.0, (y1 - y0 + 3) as f32 * cell.1);
+        self.fit(corner, size);
+    }
+
+    /// Copies what the window shows of an RGBA image into an RGBA frame the
+    /// size of the window. Window pixels off the image are black.
+    pub fn render(&self, image: &[u8], frame: &mut [u8]) {
+        let width = self.window.0 as usize;
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let point = ((i % width) as f32 + 0.5, (i / width) as f32 + 0.5);
+            match self.pixel_at(point) {
+                Some((x, y)) => {
+                    let j = (y as usize * self.image.width as usize + x as usize) * 4;
+                    pixel.copy_from_slice(&image[j..j + 4]);
+                }
+                None => pixel.copy_from_slice(&[0, 0, 0, 255]),
+            }
+        }
+    }
+}
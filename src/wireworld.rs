@@ -0,0 +1,186 @@
+use rand::Rng;
+
+use crate::{Color, ColoredDataType, DataType, Pattern, PrintableDataType, RandomInit, RuleSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WireCell {
+    Empty,
+    Conductor,
+    Head,
+    Tail,
+}
+
+impl DataType for WireCell {}
+
+impl ColoredDataType for WireCell {
+    fn get_color(&self) -> Color {
+        match self {
+            WireCell::Empty => (0, 0, 0, 255),
+            WireCell::Conductor => (255, 200, 0, 255),
+            WireCell::Head => (0, 100, 255, 255),
+            WireCell::Tail => (255, 40, 0, 255),
+        }
+    }
+}
+
+impl PrintableDataType for WireCell {
+    fn get_char(&self) -> char {
+        match self {
+            WireCell::Empty => ' ',
+            WireCell::Conductor => '#',
+            WireCell::Head => '@',
+            WireCell::Tail => '~',
+        }
+    }
+}
+
+impl RandomInit for WireCell {
+    fn rnd(rng: &mut impl Rng) -> Self {
+        match rng.gen_range(0..20) {
+            0..=9 => WireCell::Empty,
+            10 => WireCell::Head,
+            _ => WireCell::Conductor,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct WireWorld {}
+
+impl RuleSet for WireWorld {
+    type Data = WireCell;
+    const SOURCE_SIZE: u8 = 3;
+
+    fn next(&self, source: &[&Self::Data]) -> Self::Data {
+        match source[4] {
+            WireCell::Empty => WireCell::Empty,
+            WireCell::Head => WireCell::Tail,
+            WireCell::Tail => WireCell::Conductor,
+            WireCell::Conductor => {
+                let heads = source.iter().filter(|c| ***c == WireCell::Head).count();
+                if heads == 1 || heads == 2 {
+                    WireCell::Head
+                } else {
+                    WireCell::Conductor
+                }
+            }
+        }
+    }
+
+    /// In Golly's order, so WireWorld RLE files can be stamped as they are.
+    fn palette(&self) -> Vec<Self::Data> {
+        vec![
+            WireCell::Empty,
+            WireCell::Head,
+            WireCell::Tail,
+            WireCell::Conductor,
+        ]
+    }
+}
+
+impl WireWorld {
+    pub fn example(name: &str) -> Option<Pattern> {
+        EXAMPLES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, text)| Pattern::parse(text).unwrap())
+    }
+}
+
+/// Example circuits as plain text patterns with `1` head, `2` tail and `3`
+/// conductor, all signals flowing left to right. The clocks emit one
+/// electron every 10 and 18 generations.
+pub const EXAMPLES: &[(&str, &str)] = &[
+    ("diode", "........33\n333333333.3333333333\n........33\n"),
+    ("or", "333333\n......3\n.....3333333333\n......3\n333333\n"),
+    (
+        "xor",
+        "333333\n......3\n.....3333\n.....3..333333333\n.....3333\n......3\n333333\n",
+    ),
+    ("clock-10", ".1333\n2....3\n.3333.3333333333\n"),
+    ("clock-18", ".13333333\n2........3\n.33333333.3333333333\n"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, IndexType};
+
+    fn circuit(name: &str) -> Game<WireWorld> {
+        let pattern = WireWorld::example(name).unwrap();
+        let (width, height) = (pattern.width() + 2, pattern.height() + 2);
+        let data = vec![WireCell::Empty; width as usize * height as usize];
+        let mut game = Game::init_with_rules(WireWorld::default(), data, width).unwrap();
+        game.stamp(&pattern, (1, 1)).unwrap();
+        game
+    }
+
+    /// Sends electrons in at the `inputs`, each heading away from its tail,
+    /// and returns the generations at which a head reaches `output`.
+    fn heads_at(
+        game: &mut Game<WireWorld>,
+        inputs: &[(IndexType, IndexType)],
+        output: IndexType,
+        generations: u64,
+    ) -> Vec<u64> {
+        for &(head, tail) in inputs {
+            game.set_brush(1);
+            game.paint((head.0 + 1, head.1 + 1));
+            game.set_brush(2);
+            game.paint((tail.0 + 1, tail.1 + 1));
+        }
+        let mut arrivals = Vec::new();
+        for generation in 1..=generations {
+            game.next_step();
+            if *game.get_view_cell((output.0 + 1, output.1 + 1)) == WireCell::Head {
+                arrivals.push(generation);
+            }
+        }
+        arrivals
+    }
+
+    #[test]
+    fn diode_passes_one_way() {
+        let forward = heads_at(&mut circuit("diode"), &[((1, 1), (0, 1))], (19, 1), 40);
+        assert_eq!(forward.len(), 1);
+        let backward = [((18, 1), (19, 1))];
+        assert_eq!(
+            heads_at(&mut circuit("diode"), &backward, (11, 1), 40).len(),
+            1
+        );
+        assert!(heads_at(&mut circuit("diode"), &backward, (0, 1), 40).is_empty());
+    }
+
+    #[test]
+    fn or_gate() {
+        let (a, b) = (((1, 0), (0, 0)), ((1, 4), (0, 4)));
+        for inputs in [vec![a], vec![b], vec![a, b]] {
+            let arrivals = heads_at(&mut circuit("or"), &inputs, (14, 2), 30);
+            assert_eq!(arrivals.len(), 1, "{:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn xor_gate() {
+        let (a, b) = (((1, 0), (0, 0)), ((1, 6), (0, 6)));
+        for inputs in [vec![a], vec![b]] {
+            let arrivals = heads_at(&mut circuit("xor"), &inputs, (16, 3), 40);
+            assert_eq!(arrivals.len(), 1, "{:?}", inputs);
+        }
+        assert!(heads_at(&mut circuit("xor"), &[a, b], (16, 3), 40).is_empty());
+    }
+
+    #[test]
+    fn clock_periods() {
+        for (name, period, output) in [("clock-10", 10, (15, 2)), ("clock-18", 18, (19, 2))] {
+            let arrivals = heads_at(&mut circuit(name), &[], output, 100);
+            assert!(arrivals.len() > 2, "{}", name);
+            assert!(
+                arrivals.windows(2).all(|w| w[1] - w[0] == period),
+                "{}: {:?}",
+                name,
+                arrivals
+            );
+        }
+    }
+}